@@ -1,75 +1,699 @@
 mod models;
 mod utils;
 
+use reqwest::Method;
 use std::error::Error;
+use std::fmt;
+use std::process::ExitCode;
+use std::time::Instant;
 
+use models::activity::Actividad;
 use models::activity_sections::Seccion;
-use utils::excel::load_actividades;
-use utils::post_request::post_request;
+use models::parse_report::{ParseReport, Severity};
+use models::run_summary::{RunSummary, SendOutcome};
+use utils::batch::{batch_body, batch_item, send_batches, ItemOutcome};
+use utils::cli::{parse_args, CliArgs, Command, ReportFormat, SendMode};
+use utils::excel::{load_actividades, sheet_names, ParseOptions};
+use utils::export::{export_secciones, ExportFormat};
+use utils::golpes::{load_golpe_catalog, GolpeCatalog};
+use utils::layout::{load_layout, SheetLayout};
+use utils::mapping::load_payload_mapping;
+use utils::openapi::{OpenApiContract, OpenApiSpec};
+use utils::payload::{actividad_payload, describe_actividad, session_payload, PayloadOptions};
+use utils::post_request::ApiClient;
+use utils::profiles::{load_profile, Profile};
+use utils::pull::{fetch_actividades, group_by_golpe};
+use utils::routing::{
+    activity_variables, check_routes, load_routing_table, resolve_destination, sheet_variables,
+    Destination, RoutingTable,
+};
+use utils::schema::{payload_schema, SchemaDocument};
+use utils::session::session_document;
+use utils::state::{update_url, ResponseFields, StateStore};
+use utils::throttle::DeferredError;
+use utils::translation_memory::{pending_translations, TranslationMemory};
+use utils::validation::{load_validation_config, validate_secciones, ValidationConfig};
+use utils::vocabulary::VocabularyOptions;
+
+/// Prints a progress message on the standard output, or on the standard error when the
+/// standard output carries the JSON summary.
+macro_rules! progress {
+    ($json_report:expr, $($arg:tt)*) => {
+        if $json_report {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let mut args = parse_args(std::env::args().skip(1))?;
+
+    // Environment profile, whose settings apply where the command line sets nothing
+    let profile = match &args.profile {
+        Some(name) => {
+            let profile = load_profile(&args.profiles_path, name)?;
+            profile.check_confirmed(
+                name,
+                matches!(args.command, Command::Send) && !args.dry_run,
+                args.confirm_protected,
+            )?;
+            eprintln!("Using the profile '{}'", name);
+            args.apply_profile(&profile);
+            if let Some(output_dir) = &profile.output_dir {
+                std::fs::create_dir_all(output_dir)?;
+            }
+            profile
+        }
+        None => Profile::default(),
+    };
+    // The HTTP client reads the credentials and certificates, so it is only built by the
+    // commands that make requests
+    let client =
+        || -> Result<ApiClient, Box<dyn Error>> { ApiClient::new(profile.client_settings()?) };
+
+    let layout = match &args.layout {
+        Some(layout_path) => load_layout(layout_path)?,
+        None => SheetLayout::default(),
+    };
+    let translation_memory = match &args.translation_memory {
+        Some(memory_path) => {
+            let memory = TranslationMemory::load(memory_path)?;
+            if memory.is_empty() {
+                eprintln!("The translation memory {} has no segments", memory_path);
+            } else {
+                progress!(
+                    args.report_format == ReportFormat::Json,
+                    "Loaded {} translation memory segments",
+                    memory.len()
+                );
+            }
+            Some(memory)
+        }
+        None => None,
+    };
+    let options = ParseOptions {
+        layout,
+        translation_memory,
+        vocabulary: VocabularyOptions {
+            fold_accents: args.fold_accents,
+            autocorrect_threshold: args.autocorrect,
+        },
+        golpes: match &args.golpes {
+            Some(catalog_path) => load_golpe_catalog(catalog_path)?,
+            None => GolpeCatalog::default(),
+        },
+    };
+
+    match &args.command {
+        Command::Send => return send(&args, &options, &client()?),
+        Command::Pull {
+            endpoint,
+            format,
+            output,
+        } => pull(&client()?, endpoint, *format, output, &options)?,
+        Command::Validate {
+            rules,
+            single_sheet,
+        } => validate(&args, rules.as_deref(), *single_sheet, &options)?,
+        Command::Schema { document, output } => schema(&args, *document, output.as_deref())?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints or writes the JSON Schema of the payload.
+fn schema(
+    args: &CliArgs,
+    document: SchemaDocument,
+    output: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let payload_options = PayloadOptions {
+        phase_format: args.phase_format,
+        version: args.payload_version,
+        ..PayloadOptions::default()
+    };
+    let schema = serde_json::to_string_pretty(&payload_schema(document, &payload_options))?;
+    match output {
+        Some(output_path) => {
+            std::fs::write(output_path, schema)?;
+            println!("Schema written to {}", output_path);
+        }
+        None => println!("{}", schema),
+    }
+    Ok(())
+}
+
+/// Checks the activities of the workbook against the validation rules.
+fn validate(
+    args: &CliArgs,
+    rules: Option<&str>,
+    single_sheet: bool,
+    options: &ParseOptions,
+) -> Result<(), Box<dyn Error>> {
+    let config = match rules {
+        Some(rules_path) => load_validation_config(rules_path)?,
+        None => ValidationConfig::default(),
+    };
+
+    // Validate every sheet so that ids can be checked across sheets
+    let sheets = if single_sheet {
+        vec![args.sheet.clone()]
+    } else {
+        sheet_names(&args.path)?
+    };
+
+    let mut secciones = Vec::new();
+    for sheet in &sheets {
+        let (seccion, report) = load_actividades(&args.path, sheet, options)?;
+        if !report.is_empty() {
+            eprintln!("{}", report);
+        }
+        secciones.push(seccion);
+    }
+
+    write_pending_translations(args, &secciones, &options.layout)?;
+
+    let report = validate_secciones(&secciones, &config, &options.layout)?;
+    match args.report_format {
+        ReportFormat::Text => println!("{}", report),
+        ReportFormat::Json => println!("{}", report.to_json()?),
+    }
+
+    if report.has_errors() {
+        return Err(format!(
+            "Validation failed with {} errors",
+            report.count(Severity::Error)
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Downloads the activities from the server and exports them grouped by golpe.
+fn pull(
+    client: &ApiClient,
+    endpoint: &str,
+    format: ExportFormat,
+    output: &str,
+    options: &ParseOptions,
+) -> Result<(), Box<dyn Error>> {
+    let actividades = fetch_actividades(client, endpoint)?;
+    println!("Downloaded {} activities", actividades.len());
+
+    let secciones = group_by_golpe(actividades, &options.golpes);
+    export_secciones(&secciones, format, output, &options.layout)?;
+    println!("Exported {} sections to {}", secciones.len(), output);
+
+    Ok(())
+}
+
+/// Loads the activities from the workbook and sends them to the API.
+fn send(
+    args: &CliArgs,
+    options: &ParseOptions,
+    client: &ApiClient,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let started = Instant::now();
+    let json_report = args.report_format == ReportFormat::Json;
+
+    // URL template of the endpoint where the activities will be sent, and the routes of
+    // the phases and sheets that go elsewhere
+    let endpoint = args.endpoint.as_deref().unwrap_or("");
+    let routes = match &args.routes {
+        Some(routes_path) => Some(load_routing_table(routes_path)?),
+        None => None,
+    };
+    let (default_endpoint, phases) = match args.send_mode {
+        SendMode::Activities => (endpoint, options.layout.phases().map(Some).collect()),
+        SendMode::Batch => (
+            args.bulk_endpoint.as_deref().unwrap_or(endpoint),
+            vec![None],
+        ),
+        SendMode::Session => (endpoint, vec![None]),
+    };
+    check_routes(routes.as_ref(), default_endpoint, &args.sheet, &phases)?;
 
-fn main() -> Result<(), Box<dyn Error>> {
     // Load activities from the Excel file
-    let path = "programming-table-2.xlsx";
+    let path = args.path.as_str();
 
     if !std::path::Path::new(path).exists() {
-        println!("File not found at: {}", path);
-        return Ok(());
+        return Err(format!("File not found at: {}", path).into());
     }
 
     // Load activities from the specified sheet
-    let seccion: Seccion = load_actividades(path, "1. DERECHA PLANA")?;
-    println!("{:#?}", seccion);
-
-    // URL of the endpoint where the activities will be sent
-    let endpoint =
-        "";
-
-    // Send warming-up activities
-    for actividad in seccion.calentamiento {
-        let actividad_json = serde_json::to_string(&actividad)?; // Convert the activity to JSON
-        println!("Sending activity: {}", actividad.id);
-
-        // Send the POST request
-        match post_request(&actividad_json, endpoint) {
-            Ok(response) => println!("Activity sent successfully: {}", response),
-            Err(e) => eprintln!("Error sending activity '{}': {}", actividad.phase, e),
-        }
+    let (seccion, report): (Seccion, ParseReport) = load_actividades(path, &args.sheet, options)?;
+    progress!(json_report, "{}", serde_json::to_string_pretty(&seccion)?);
+
+    // Show the issues found while parsing, and save them as JSON if requested
+    if !report.is_empty() {
+        eprintln!("{}", report);
+    }
+    if let Some(report_path) = &args.parse_report {
+        std::fs::write(report_path, report.to_json()?)?;
+    }
+    write_pending_translations(args, std::slice::from_ref(&seccion), &options.layout)?;
+    let mut summary = RunSummary {
+        parse_errors: report.count(Severity::Error),
+        dry_run: args.dry_run,
+        ..RunSummary::default()
+    };
+    for phase in report.skipped_blocks() {
+        summary.record_skipped_block(&args.sheet, phase);
     }
 
-    // Send first set of exercises
-    for actividad in seccion.ejercicio1 {
-        let actividad_json = serde_json::to_string(&actividad)?; // Convert the activity to JSON
-        println!("Sending activity: {}", actividad.id);
+    let base = args
+        .base_url
+        .as_deref()
+        .or(routes.as_ref().and_then(|routes| routes.base.as_deref()));
+    let golpe = seccion.golpe.as_ref().map(|golpe| golpe.id);
+
+    // OpenAPI specification the requests and responses are checked against
+    let openapi = match &args.openapi {
+        Some(openapi_path) => Some(OpenApiSpec::load(openapi_path)?),
+        None => None,
+    };
+
+    // Server ids of the activities sent in previous runs, which are updated instead of created
+    let mut state = match &args.state {
+        Some(state_path) => Some(StateStore::open(
+            state_path,
+            ResponseFields {
+                server_id: args.server_id_field.clone(),
+                fields: args.response_fields.clone(),
+            },
+        )?),
+        None => None,
+    };
 
-        // Send the POST request
-        match post_request(&actividad_json, endpoint) {
-            Ok(response) => println!("Activity sent successfully: {}", response),
-            Err(e) => eprintln!("Error sending activity '{}': {}", actividad.phase, e),
+    let payload_options = PayloadOptions {
+        include_provenance: args.include_provenance,
+        phase_format: args.phase_format,
+        version: args.payload_version,
+        mapping: match &args.mapping {
+            Some(mapping_path) => Some(load_payload_mapping(mapping_path)?),
+            None => None,
+        },
+    };
+    let activity_schema = payload_schema(SchemaDocument::Activity, &payload_options);
+
+    let target = SendTarget {
+        client,
+        endpoint,
+        routes: routes.as_ref(),
+        base,
+        sheet: &args.sheet,
+        openapi: openapi.as_ref(),
+        dry_run: args.dry_run,
+        json_report,
+    };
+
+    match args.send_mode {
+        SendMode::Activities => {
+            // Send the activities of each phase, in session order
+            for fase in &seccion.fases {
+                send_actividades(
+                    &fase.actividades,
+                    &target,
+                    &payload_options,
+                    &activity_schema,
+                    state.as_mut(),
+                    &mut summary,
+                )?;
+            }
+        }
+        SendMode::Batch => {
+            // Send the activities of every phase in arrays of up to batch_size activities
+            let destination = resolve_destination(
+                target.routes,
+                args.bulk_endpoint.as_deref().unwrap_or(endpoint),
+                target.sheet,
+                None,
+                &sheet_variables(target.sheet, golpe, base),
+            )?;
+            let contract = match target.openapi {
+                Some(spec) => Some(spec.contract(&destination.url, destination.method.as_str())?),
+                None => None,
+            };
+            let mut items = Vec::new();
+            for actividad in seccion.actividades() {
+                match batch_item(actividad, &payload_options, &activity_schema) {
+                    Ok(item) => items.push(item),
+                    Err(e) => {
+                        eprintln!("Skipping activity {}: {}", describe_actividad(actividad), e);
+                        summary.record(target.sheet, actividad.phase, SendOutcome::Skipped);
+                    }
+                }
+            }
+            if args.dry_run {
+                eprintln!("{} {}", destination.method, destination.url);
+                for batch in items.chunks(args.batch_size) {
+                    progress!(json_report, "{}", batch_body(batch));
+                }
+            } else {
+                let retries = client.retries();
+                let results = send_batches(
+                    client,
+                    &items,
+                    &destination,
+                    args.batch_size,
+                    contract.as_ref(),
+                );
+                summary.record_retries(target.sheet, None, client.retries() - retries);
+                for result in &results {
+                    let outcome = match &result.outcome {
+                        ItemOutcome::Sent(item) => {
+                            if let Some(item) = item {
+                                progress!(
+                                    json_report,
+                                    "Activity {} sent successfully: {}",
+                                    result.label,
+                                    item
+                                );
+                                if let Some(state) = state.as_mut() {
+                                    state.record(&result.id, item);
+                                }
+                            }
+                            SendOutcome::Sent
+                        }
+                        ItemOutcome::Failed(message) => {
+                            eprintln!("Error sending activity {}: {}", result.label, message);
+                            SendOutcome::Failed
+                        }
+                        ItemOutcome::Deferred(message) => {
+                            eprintln!("Deferred activity {}: {}", result.label, message);
+                            SendOutcome::Deferred
+                        }
+                    };
+                    summary.record(target.sheet, result.phase, outcome);
+                }
+            }
+        }
+        SendMode::Session => {
+            // Send the whole sheet as a single session document
+            let destination = resolve_destination(
+                target.routes,
+                endpoint,
+                target.sheet,
+                None,
+                &sheet_variables(target.sheet, golpe, base),
+            )?;
+            let contract = match target.openapi {
+                Some(spec) => Some(spec.contract(&destination.url, destination.method.as_str())?),
+                None => None,
+            };
+            let document = session_document(&seccion);
+            progress!(
+                json_report,
+                "Sending session: {} activities, {} minutes",
+                document.totals.activities,
+                document.totals.duration_minutes
+            );
+            let subject = format!("Session {}", args.sheet);
+            let session_json = match session_payload(&document, &payload_options) {
+                Ok(session_json) => Some(session_json),
+                Err(e) => {
+                    eprintln!("Error building session {}: {}", args.sheet, e);
+                    None
+                }
+            };
+            let rejected = match (&contract, &session_json) {
+                (Some(contract), Some(session_json)) => {
+                    let violations = contract.check_request(session_json);
+                    report_violations(&subject, "request body", contract, &violations)
+                }
+                _ => false,
+            };
+            let outcome = match session_json {
+                None => Some(SendOutcome::Failed),
+                Some(_) if rejected => {
+                    eprintln!("{} does not match the OpenAPI contract, not sent", subject);
+                    Some(SendOutcome::Skipped)
+                }
+                Some(session_json) if args.dry_run => {
+                    eprintln!("{} {}", destination.method, destination.url);
+                    progress!(json_report, "{}", session_json);
+                    None
+                }
+                Some(session_json) => {
+                    let retries = client.retries();
+                    let outcome =
+                        match client.send(destination.method, &session_json, &destination.url) {
+                            Ok(response) => {
+                                progress!(json_report, "Session sent successfully: {}", response);
+                                if let Some(contract) = &contract {
+                                    let violations =
+                                        contract.check_response(response.status, &response.body);
+                                    report_violations(&subject, "response", contract, &violations);
+                                }
+                                SendOutcome::Sent
+                            }
+                            Err(e) if e.is::<DeferredError>() => {
+                                eprintln!("Deferred session {}: {}", args.sheet, e);
+                                SendOutcome::Deferred
+                            }
+                            Err(e) => {
+                                eprintln!("Error sending session {}: {}", args.sheet, e);
+                                SendOutcome::Failed
+                            }
+                        };
+                    summary.record_retries(target.sheet, None, client.retries() - retries);
+                    Some(outcome)
+                }
+            };
+            // In a dry run the valid payloads are only printed, like in the other modes
+            if let Some(outcome) = outcome {
+                for actividad in seccion.actividades() {
+                    summary.record(target.sheet, actividad.phase, outcome);
+                }
+            }
         }
     }
 
-    // Send second set of exercises
-    for actividad in seccion.ejercicio2 {
-        let actividad_json = serde_json::to_string(&actividad)?; // Convert the activity to JSON
-        println!("Sending activity: {}", actividad.id);
+    if let Some(state) = state.as_ref().filter(|_| !args.dry_run) {
+        state.save()?;
+    }
 
-        // Send the POST request
-        match post_request(&actividad_json, endpoint) {
-            Ok(response) => println!("Activity sent successfully: {}", response),
-            Err(e) => eprintln!("Error sending activity '{}': {}", actividad.phase, e),
-        }
+    let compression = client.compression_stats();
+    if compression.requests > 0 {
+        summary.compression = Some(compression);
+    }
+    summary.duration_secs = started.elapsed().as_secs_f64();
+    match args.report_format {
+        ReportFormat::Text => println!("{}", summary),
+        ReportFormat::Json => println!("{}", summary.to_json()?),
     }
 
-    // Send final phase activities
-    for actividad in seccion.parte_final {
-        let actividad_json = serde_json::to_string(&actividad)?; // Convert the activity to JSON
-        println!("Sending activity: {}", actividad.id);
+    Ok(ExitCode::from(summary.exit_code()))
+}
+
+/// Where the activities of the send command go.
+struct SendTarget<'a> {
+    /// HTTP client of the requests.
+    client: &'a ApiClient,
+
+    /// URL template where the activities without a route are created.
+    endpoint: &'a str,
+
+    /// Routes of the phases and sheets sent to other endpoints.
+    routes: Option<&'a RoutingTable>,
+
+    /// Value of the `{base}` placeholder of the URL templates.
+    base: Option<&'a str>,
+
+    /// Name of the sheet being sent.
+    sheet: &'a str,
 
-        // Send the POST request
-        match post_request(&actividad_json, endpoint) {
-            Ok(response) => println!("Activity sent successfully: {}", response),
-            Err(e) => eprintln!("Error sending activity '{}': {}", actividad.phase, e),
+    /// OpenAPI specification the requests and responses are checked against.
+    openapi: Option<&'a OpenApiSpec>,
+
+    /// Print the payloads instead of sending them.
+    dry_run: bool,
+
+    /// Print the progress on the standard error, as the standard output carries the JSON summary.
+    json_report: bool,
+}
+
+/// Writes the texts that still need a human translation, if requested.
+fn write_pending_translations(
+    args: &CliArgs,
+    secciones: &[Seccion],
+    layout: &SheetLayout,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(pending_path) = &args.pending_translations {
+        let targets: Vec<&str> = layout
+            .languages
+            .iter()
+            .skip(1)
+            .map(|language| language.code.as_str())
+            .collect();
+        let pending = pending_translations(secciones, layout.primary_language(), &targets);
+        std::fs::write(pending_path, serde_json::to_string_pretty(&pending)?)?;
+        progress!(
+            args.report_format == ReportFormat::Json,
+            "{} texts need a human translation, listed in {}",
+            pending.len(),
+            pending_path
+        );
+    }
+    Ok(())
+}
+
+/// Prints the OpenAPI contract violations of a request or response body.
+///
+/// Returns `true` if there were violations.
+fn report_violations(
+    subject: &str,
+    body: &str,
+    contract: &OpenApiContract,
+    violations: &[String],
+) -> bool {
+    if violations.is_empty() {
+        return false;
+    }
+    eprintln!(
+        "{}: the {} does not match {}:",
+        subject, body, contract.operation
+    );
+    for violation in violations {
+        eprintln!("  {}", violation);
+    }
+    true
+}
+
+/// Sends each activity of a phase with its own request.
+///
+/// The destination of each activity comes from the routing table, or from the endpoint
+/// template when no route matches. Activities with a server id in the state store are
+/// updated with a PUT request to their URL instead, and the response fields of every
+/// request are stored. Activities whose payload does not match the OpenAPI contract are
+/// skipped, and the responses are checked against it. With `dry_run` the payloads are
+/// printed instead of sent. The outcome of each activity is recorded in the summary.
+fn send_actividades(
+    actividades: &[Actividad],
+    target: &SendTarget,
+    payload_options: &PayloadOptions,
+    activity_schema: &serde_json::Value,
+    mut state: Option<&mut StateStore>,
+    summary: &mut RunSummary,
+) -> Result<(), Box<dyn Error>> {
+    for actividad in actividades {
+        let subject = format!("Activity {}", describe_actividad(actividad));
+        let mut skip = |e: &dyn fmt::Display| {
+            eprintln!("Skipping activity {}: {}", describe_actividad(actividad), e);
+            summary.record(target.sheet, actividad.phase, SendOutcome::Skipped);
+        };
+
+        // Convert the activity to JSON, skipping it if it does not match the schema
+        let actividad_json = match actividad_payload(actividad, payload_options, activity_schema) {
+            Ok(actividad_json) => actividad_json,
+            Err(e) => {
+                skip(&e);
+                continue;
+            }
+        };
+
+        // Find where the activity goes, and the contract of that operation
+        let variables = activity_variables(actividad, target.sheet, target.base);
+        let server_id = state
+            .as_deref()
+            .and_then(|state| state.server_id(&actividad.id));
+        let destination = resolve_destination(
+            target.routes,
+            target.endpoint,
+            target.sheet,
+            Some(actividad.phase),
+            &variables,
+        )
+        .map(|destination| match server_id {
+            Some(server_id) => Destination {
+                method: Method::PUT,
+                url: update_url(&destination.url, server_id),
+            },
+            None => destination,
+        });
+        let destination = match destination {
+            Ok(destination) => destination,
+            Err(e) => {
+                skip(&e);
+                continue;
+            }
+        };
+        let contract = match target
+            .openapi
+            .map(|spec| spec.contract(&destination.url, destination.method.as_str()))
+            .transpose()
+        {
+            Ok(contract) => contract,
+            Err(e) => {
+                skip(&e);
+                continue;
+            }
+        };
+        if let Some(contract) = &contract {
+            let violations = contract.check_request(&actividad_json);
+            if report_violations(&subject, "request body", contract, &violations) {
+                summary.record(target.sheet, actividad.phase, SendOutcome::Skipped);
+                continue;
+            }
+        }
+
+        if target.dry_run {
+            eprintln!("{} {}", destination.method, destination.url);
+            progress!(target.json_report, "{}", actividad_json);
+            continue;
         }
+        progress!(
+            target.json_report,
+            "Sending activity: {} ({} {})",
+            describe_actividad(actividad),
+            destination.method,
+            destination.url
+        );
+
+        // Send the request
+        let retries = target.client.retries();
+        let outcome =
+            match target
+                .client
+                .send(destination.method, &actividad_json, &destination.url)
+            {
+                Ok(response) => {
+                    progress!(
+                        target.json_report,
+                        "Activity sent successfully: {}",
+                        response
+                    );
+                    if let Some(contract) = &contract {
+                        let violations = contract.check_response(response.status, &response.body);
+                        report_violations(&subject, "response", contract, &violations);
+                    }
+                    if let Some(state) = state.as_deref_mut() {
+                        if let Err(e) = state.record_body(&actividad.id, &response.body) {
+                            eprintln!("{}: the response was not stored: {}", subject, e);
+                        }
+                    }
+                    SendOutcome::Sent
+                }
+                Err(e) if e.is::<DeferredError>() => {
+                    eprintln!("Deferred activity {}: {}", describe_actividad(actividad), e);
+                    SendOutcome::Deferred
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Error sending activity {}: {}",
+                        describe_actividad(actividad),
+                        e
+                    );
+                    SendOutcome::Failed
+                }
+            };
+        summary.record(target.sheet, actividad.phase, outcome);
+        summary.record_retries(
+            target.sheet,
+            Some(actividad.phase),
+            target.client.retries() - retries,
+        );
     }
 
     Ok(())
@@ -1,5 +1,8 @@
 use super::content::Content;
-use serde::Serialize;
+use super::phase::Phase;
+use super::players::Jugadores;
+use super::provenance::Provenance;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Represents an activity within the application.
@@ -7,7 +10,7 @@ use std::collections::HashMap;
 /// This structure contains detailed information about an activity,
 /// including its ID, type of shot, players involved, level, and other
 /// aspects related to its content and configuration.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Actividad {
     /// Unique identifier for the activity.
     pub id: String,
@@ -15,11 +18,11 @@ pub struct Actividad {
     /// Type of shot associated with the activity.
     pub golpe: i32,
 
-    /// Phase of shot associated with the activity.
-    pub phase: String,
+    /// Phase of the session the activity belongs to.
+    pub phase: Phase,
 
-    /// List of player IDs involved in the activity.
-    pub num_jugadores: Vec<i32>,
+    /// Player counts the activity is designed for.
+    pub num_jugadores: Jugadores,
 
     /// Player typologies the activity is designed for.
     pub typology: Vec<String>,
@@ -45,4 +48,9 @@ pub struct Actividad {
     /// Associated content for the activity, represented as a key-value map.
     /// Keys are strings, and values are instances of `Content`.
     pub content: HashMap<String, Content>,
+
+    /// Location of the activity in the workbook it was read from.
+    /// It is kept out of the API payload unless explicitly requested.
+    #[serde(skip)]
+    pub provenance: Option<Provenance>,
 }
@@ -1,20 +1,72 @@
 use super::activity::Actividad;
+use super::golpe::Golpe;
+use super::phase::Phase;
+use serde::{Deserialize, Serialize};
+
+/// Activities of one phase block of a section.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Fase {
+    /// The phase of the block.
+    pub phase: Phase,
+
+    /// Activities of the block, in sheet order.
+    pub actividades: Vec<Actividad>,
+}
 
 /// Represents a section of an activity, which contains different stages or phases.
 ///
 /// This structure is used to organize an activity into distinct parts, such as
-/// warming up, performing exercises, and finishing with a final phase.
-#[derive(Debug)]
+/// warming up, performing exercises, and finishing with a final phase. The number of
+/// phases depends on the layout of the sheet.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Seccion {
-    /// List of activities for the warm-up phase of the section.
-    pub calentamiento: Vec<Actividad>,
+    /// Stroke programmed in the section, when it is in the golpe catalog.
+    pub golpe: Option<Golpe>,
+
+    /// Phases of the section, in session order.
+    pub fases: Vec<Fase>,
+}
 
-    /// List of activities for the first exercise phase of the section.
-    pub ejercicio1: Vec<Actividad>,
+impl Seccion {
+    /// Creates a section with an empty block for each phase, in the given order.
+    pub fn new(golpe: Option<Golpe>, phases: impl IntoIterator<Item = Phase>) -> Seccion {
+        Seccion {
+            golpe,
+            fases: phases
+                .into_iter()
+                .map(|phase| Fase {
+                    phase,
+                    actividades: Vec::new(),
+                })
+                .collect(),
+        }
+    }
 
-    /// List of activities for the second exercise phase of the section.
-    pub ejercicio2: Vec<Actividad>,
+    /// Returns the activities of a phase, adding the phase in session order if it is missing.
+    pub fn fase_mut(&mut self, phase: Phase) -> &mut Vec<Actividad> {
+        let position = match self.fases.iter().position(|fase| fase.phase == phase) {
+            Some(position) => position,
+            None => {
+                let position = self
+                    .fases
+                    .iter()
+                    .position(|fase| fase.phase > phase)
+                    .unwrap_or(self.fases.len());
+                self.fases.insert(
+                    position,
+                    Fase {
+                        phase,
+                        actividades: Vec::new(),
+                    },
+                );
+                position
+            }
+        };
+        &mut self.fases[position].actividades
+    }
 
-    /// List of activities for the final phase of the section.
-    pub parte_final: Vec<Actividad>,
+    /// Iterates over the activities of every phase, in phase order.
+    pub fn actividades(&self) -> impl Iterator<Item = &Actividad> {
+        self.fases.iter().flat_map(|fase| &fase.actividades)
+    }
 }
@@ -15,4 +15,9 @@ pub struct Content {
     /// Script associated with the content, which could be instructions or details
     /// about how to perform or understand the content.
     pub script: String,
+
+    /// Whether some of the fields were filled from the translation memory instead of
+    /// being written by a translator.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub machine_filled: bool,
 }
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// A stroke of the programming, as listed in the golpe catalog.
+///
+/// Each sheet of the workbook programs one stroke; its name starts with the golpe id
+/// (e.g., "1. DERECHA PLANA").
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Golpe {
+    /// Numeric id of the stroke, as written in column A and in the sheet name.
+    pub id: i32,
+
+    /// Spanish name of the stroke, as used in the sheet names.
+    pub name_es: String,
+
+    /// English name of the stroke.
+    pub name_en: String,
+
+    /// Code of the stroke in the API (e.g., "FLAT_FOREHAND").
+    pub api_code: String,
+}
@@ -5,11 +5,51 @@
 pub mod activity;
 
 /// Module defining the structure for activity sections in a training program.
-/// This module includes the `Seccion` struct, which organizes different types of activities
-/// (e.g., calentamiento, ejercicio1, ejercicio2, parte_final) within the context of a session.
+/// This module includes the `Seccion` struct, which organizes the activities of a session
+/// in an ordered list of phases (e.g., calentamiento, ejercicio 1, ejercicio 2, parte final).
 pub mod activity_sections;
 
 /// Module containing content information for training activities.
 /// This module defines the `Content` struct, which holds information like the title,
 /// objective, and script for each activity in different languages (e.g., Spanish, English).
 pub mod content;
+
+/// Module describing the strokes of the programming.
+/// This module defines the `Golpe` struct, which holds the id, the Spanish and English
+/// names and the API code of each stroke of the golpe catalog.
+pub mod golpe;
+
+/// Module defining the phases of a session.
+/// This module includes the `Phase` enum (warm-up, numbered main exercises and final
+/// part) and the `PhaseFormat` used to write it in the API payload.
+pub mod phase;
+
+/// Module modelling the number of players of an activity.
+/// This module defines the `Jugadores` struct, which holds the allowed player counts
+/// read from single numbers, ranges and words, and serializes them as a list.
+pub mod players;
+
+/// Module describing where a parsed activity comes from in the workbook.
+/// This module defines the `Provenance` struct, which records the file, sheet, row and
+/// block column range of each `Actividad` so it can be located from logs and reports.
+pub mod provenance;
+
+/// Module defining the session document sent to the API.
+/// This module includes the `SessionDocument` struct, which holds the golpe, the phases
+/// in order with their activities, and the duration and equipment totals of a section.
+pub mod session;
+
+/// Module collecting the issues found while parsing a sheet.
+/// This module defines the `ParseReport` struct, which lists skipped rows, short rows,
+/// empty blocks, defaulted values and untranslated terms, each with its location.
+pub mod parse_report;
+
+/// Module holding the result of the validation rules.
+/// This module defines the `ValidationReport` struct, which lists the rule violations
+/// found on each activity with their severity and cell reference.
+pub mod validation_report;
+
+/// Module summarizing a send run.
+/// This module defines the `RunSummary` struct, which counts the activities sent, skipped,
+/// failed and deferred per sheet and phase, and the exit code of the run.
+pub mod run_summary;
@@ -0,0 +1,253 @@
+use super::phase::Phase;
+use super::provenance::Provenance;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How serious a parse issue or a validation finding is.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Informational note, nothing was lost.
+    Info,
+    /// Data was defaulted or passed through untranslated.
+    Warning,
+    /// Data was dropped.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Error => "ERROR",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Kind of issue found while parsing a sheet.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IssueKind {
+    /// A row was skipped entirely (e.g., a blank row).
+    SkippedRow,
+    /// A row has fewer cells than the layout requires.
+    ShortRow,
+    /// A phase block has no data and was skipped.
+    EmptyBlock,
+    /// A phase block fills only some of the required fields and was skipped.
+    PartialBlock,
+    /// A value could not be parsed and a default was used instead.
+    DefaultedValue,
+    /// The golpe of column A and the golpe of the sheet name disagree.
+    GolpeMismatch,
+    /// A golpe id has no entry in the golpe catalog.
+    UnknownGolpe,
+    /// A term has no entry in the translation tables.
+    UntranslatedTerm,
+    /// An unknown term was replaced by the closest known term.
+    CorrectedTerm,
+    /// A content field was filled from the translation memory.
+    MachineTranslation,
+    /// An activity lacks the content of one of the languages of the layout.
+    MissingTranslation,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            IssueKind::SkippedRow => "skipped row",
+            IssueKind::ShortRow => "short row",
+            IssueKind::EmptyBlock => "empty block",
+            IssueKind::PartialBlock => "partial block",
+            IssueKind::DefaultedValue => "defaulted value",
+            IssueKind::GolpeMismatch => "golpe mismatch",
+            IssueKind::UnknownGolpe => "unknown golpe",
+            IssueKind::UntranslatedTerm => "untranslated term",
+            IssueKind::CorrectedTerm => "corrected term",
+            IssueKind::MachineTranslation => "machine translation",
+            IssueKind::MissingTranslation => "missing translation",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A single issue found while parsing a sheet, with its location in the workbook.
+#[derive(Debug, Serialize, Clone)]
+pub struct ParseIssue {
+    /// Kind of issue.
+    pub kind: IssueKind,
+
+    /// How serious the issue is.
+    pub severity: Severity,
+
+    /// Row, block or cell where the issue was found.
+    pub location: Provenance,
+
+    /// Activity field affected by the issue, if any (e.g., "golpe").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+
+    /// Phase of the block that was skipped, for the issues that drop a whole block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<Phase>,
+
+    /// Human readable description of the issue.
+    pub message: String,
+}
+
+impl fmt::Display for ParseIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {} at {}", self.severity, self.kind, self.location)?;
+        if let Some(field) = &self.field {
+            write!(f, " ({})", field)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Collects the issues found while loading a sheet.
+///
+/// The report is returned next to the `Seccion` by `load_actividades` and can be
+/// printed as text (through `Display`) or serialized as JSON.
+#[derive(Debug, Serialize, Default)]
+pub struct ParseReport {
+    /// Issues in the order they were found.
+    pub issues: Vec<ParseIssue>,
+}
+
+impl ParseReport {
+    /// Records a new issue.
+    ///
+    /// Identical issues (e.g., from the columns shared by every block of a row) are
+    /// recorded once.
+    pub fn push(
+        &mut self,
+        kind: IssueKind,
+        severity: Severity,
+        location: Provenance,
+        field: Option<&str>,
+        message: impl Into<String>,
+    ) {
+        self.insert(ParseIssue {
+            kind,
+            severity,
+            location,
+            field: field.map(str::to_string),
+            phase: None,
+            message: message.into(),
+        });
+    }
+
+    /// Records an issue that drops a whole phase block, so that the block is counted as
+    /// skipped in the run summary.
+    pub fn push_skipped_block(
+        &mut self,
+        kind: IssueKind,
+        severity: Severity,
+        location: Provenance,
+        phase: Phase,
+        message: impl Into<String>,
+    ) {
+        self.insert(ParseIssue {
+            kind,
+            severity,
+            location,
+            field: None,
+            phase: Some(phase),
+            message: message.into(),
+        });
+    }
+
+    fn insert(&mut self, issue: ParseIssue) {
+        let duplicate = self.issues.iter().any(|existing| {
+            existing.kind == issue.kind
+                && existing.location == issue.location
+                && existing.field == issue.field
+                && existing.message == issue.message
+        });
+        if !duplicate {
+            self.issues.push(issue);
+        }
+    }
+
+    /// Returns `true` if no issue was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Phases of the blocks dropped for parse errors, one per block. Blank blocks are left
+    /// out, as they hold no activity.
+    pub fn skipped_blocks(&self) -> impl Iterator<Item = Phase> + '_ {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .filter_map(|issue| issue.phase)
+    }
+
+    /// Number of issues with the given severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    /// Serializes the report as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl fmt::Display for ParseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        write!(
+            f,
+            "{} errors, {} warnings, {} notes",
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Info)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_blocks_leave_out_the_blank_ones() {
+        let location = |row| Provenance::new("es.xlsx", "1. DERECHA", row, 4, 18);
+        let mut report = ParseReport::default();
+        let main = Phase::MainExercise { index: 1 };
+        report.push_skipped_block(
+            IssueKind::EmptyBlock,
+            Severity::Info,
+            location(5),
+            Phase::WarmUp,
+            "WARM_UP block is empty, skipped",
+        );
+        report.push_skipped_block(
+            IssueKind::PartialBlock,
+            Severity::Error,
+            location(6),
+            main,
+            "MAIN_EXERCISE_1 block is missing shot, skipped",
+        );
+        report.push_skipped_block(
+            IssueKind::ShortRow,
+            Severity::Error,
+            location(7),
+            Phase::FinalPart,
+            "row has 10 cells, the block needs 19",
+        );
+        assert_eq!(
+            report.skipped_blocks().collect::<Vec<_>>(),
+            [main, Phase::FinalPart]
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// Phase of the session an activity belongs to.
+///
+/// Sessions may have several main exercise blocks, numbered from 1 in sheet order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    /// Warming-up block at the start of the session.
+    WarmUp,
+    /// Main exercise block, `index` starting at 1.
+    MainExercise { index: u32 },
+    /// Final block of the session.
+    FinalPart,
+}
+
+/// How the phase is written in the payload sent to the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhaseFormat {
+    /// "WARM_UP", "MAIN_EXERCISE" or "FINAL_PART"; every main exercise block is written
+    /// the same way, as the API expected so far.
+    #[default]
+    Legacy,
+    /// "WARM_UP", "MAIN_EXERCISE_1", "MAIN_EXERCISE_2", ... or "FINAL_PART".
+    Indexed,
+    /// An object with the phase kind and the block index, e.g.
+    /// `{ "type": "MAIN_EXERCISE", "index": 2 }`.
+    Object,
+}
+
+impl FromStr for PhaseFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "legacy" => Ok(PhaseFormat::Legacy),
+            "indexed" => Ok(PhaseFormat::Indexed),
+            "object" => Ok(PhaseFormat::Object),
+            other => Err(format!("Unknown phase format: {}", other)),
+        }
+    }
+}
+
+impl Phase {
+    /// Kind of the phase, without the block index ("WARM_UP", "MAIN_EXERCISE" or "FINAL_PART").
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Phase::WarmUp => "WARM_UP",
+            Phase::MainExercise { .. } => "MAIN_EXERCISE",
+            Phase::FinalPart => "FINAL_PART",
+        }
+    }
+
+    /// Index of the block within its kind; 1 for the warm-up and the final part.
+    pub fn index(&self) -> u32 {
+        match self {
+            Phase::MainExercise { index } => *index,
+            _ => 1,
+        }
+    }
+
+    /// Header of the phase block in the programming tables (e.g., "EJERCICIO 2").
+    pub fn sheet_label(&self) -> String {
+        match self {
+            Phase::WarmUp => "CALENTAMIENTO".to_string(),
+            Phase::MainExercise { index } => format!("EJERCICIO {}", index),
+            Phase::FinalPart => "PARTE FINAL".to_string(),
+        }
+    }
+
+    /// Writes the phase in the given wire format.
+    pub fn to_wire(self, format: PhaseFormat) -> Value {
+        match format {
+            PhaseFormat::Legacy => Value::String(self.kind().to_string()),
+            PhaseFormat::Indexed => Value::String(self.to_string()),
+            PhaseFormat::Object => json!({ "type": self.kind(), "index": self.index() }),
+        }
+    }
+
+    /// Reads a phase written in any wire format. "MAIN_EXERCISE" without an index is
+    /// read as the first main exercise block.
+    pub fn from_wire(value: &Value) -> Result<Phase, String> {
+        match value {
+            Value::String(text) => text.parse(),
+            Value::Object(object) => {
+                let kind = object
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .ok_or("Phase object without a type")?;
+                let index = object.get("index").and_then(Value::as_u64).unwrap_or(1);
+                match kind {
+                    "MAIN_EXERCISE" => format!("{}_{}", kind, index).parse(),
+                    other => other.parse(),
+                }
+            }
+            other => Err(format!("Invalid phase: {}", other)),
+        }
+    }
+}
+
+impl FromStr for Phase {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_uppercase();
+        match value.as_str() {
+            "WARM_UP" => Ok(Phase::WarmUp),
+            "FINAL_PART" => Ok(Phase::FinalPart),
+            "MAIN_EXERCISE" => Ok(Phase::MainExercise { index: 1 }),
+            other => other
+                .strip_prefix("MAIN_EXERCISE_")
+                .and_then(|index| index.parse().ok())
+                .filter(|index| *index > 0)
+                .map(|index| Phase::MainExercise { index })
+                .ok_or_else(|| format!("Unknown phase: {}", other)),
+        }
+    }
+}
+
+/// Writes the phase in the indexed format ("MAIN_EXERCISE_2").
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::MainExercise { index } => write!(f, "MAIN_EXERCISE_{}", index),
+            other => write!(f, "{}", other.kind()),
+        }
+    }
+}
+
+impl Serialize for Phase {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Phase {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Phase::from_wire(&value).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Largest player count accepted from the sheet; larger counts and ranges reaching
+/// beyond it are treated as unreadable values, as are counts below 1.
+pub const MAX_PLAYERS: i32 = 64;
+
+/// Number of players an activity is designed for.
+///
+/// The sheet may give single counts ("4 JUGADORES"), ranges ("2-4", "3 A 4") or
+/// words ("PAREJAS"); all of them are stored as ranges of allowed counts, a single
+/// count being a range of one. The ranges are expanded only when serialized, as a
+/// sorted list of integers, so the payload keeps the `[2, 3, 4]` format the API expects.
+#[derive(Debug, Clone, Default)]
+pub struct Jugadores {
+    /// Allowed player counts, as `(min, max)` ranges.
+    ranges: BTreeSet<(i32, i32)>,
+}
+
+impl Jugadores {
+    /// Adds a single player count.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the count is below 1 or larger than `MAX_PLAYERS` and was not added.
+    pub fn add(&mut self, count: i32) -> bool {
+        self.add_range(count, count)
+    }
+
+    /// Adds the range of counts `min..=max`.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the range starts below 1 or reaches beyond `MAX_PLAYERS` and was not added.
+    pub fn add_range(&mut self, min: i32, max: i32) -> bool {
+        if min < 1 || max > MAX_PLAYERS {
+            return false;
+        }
+        self.ranges.insert((min, max));
+        true
+    }
+
+    /// Smallest allowed player count.
+    pub fn min(&self) -> Option<i32> {
+        self.ranges.first().map(|(min, _)| *min)
+    }
+
+    /// Largest allowed player count.
+    pub fn max(&self) -> Option<i32> {
+        self.ranges.iter().map(|(_, max)| *max).max()
+    }
+
+    /// Iterates over the allowed player counts in ascending order, with the ranges expanded.
+    pub fn iter(&self) -> impl Iterator<Item = i32> {
+        self.ranges
+            .iter()
+            .flat_map(|(min, max)| *min..=*max)
+            .collect::<BTreeSet<i32>>()
+            .into_iter()
+    }
+}
+
+/// Two values are equal when they allow the same counts, however they were written.
+impl PartialEq for Jugadores {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Jugadores {}
+
+impl FromIterator<i32> for Jugadores {
+    fn from_iter<I: IntoIterator<Item = i32>>(iter: I) -> Self {
+        Jugadores {
+            ranges: iter.into_iter().map(|count| (count, count)).collect(),
+        }
+    }
+}
+
+impl Serialize for Jugadores {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Jugadores {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<i32>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+/// Writes the counts as in the sheet, collapsing runs of three or more into ranges ("1, 2-4").
+impl fmt::Display for Jugadores {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut runs: Vec<(i32, i32)> = Vec::new();
+        for count in self.iter() {
+            match runs.last_mut() {
+                Some((_, last)) if *last + 1 == count => *last = count,
+                _ => runs.push((count, count)),
+            }
+        }
+        let parts: Vec<String> = runs
+            .into_iter()
+            .flat_map(|(first, last)| match last - first {
+                0 => vec![first.to_string()],
+                1 => vec![first.to_string(), last.to_string()],
+                _ => vec![format!("{}-{}", first, last)],
+            })
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Location in the workbook an activity was read from.
+///
+/// This structure lets an activity rejected by the API be found again in the
+/// Excel file without searching it by id.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// Path of the Excel file.
+    pub file: String,
+
+    /// Name of the sheet containing the activity.
+    pub sheet: String,
+
+    /// 1-based row number of the activity in the sheet.
+    pub row: usize,
+
+    /// Cell range covered by the activity block (e.g., "E5:S5").
+    pub columns: String,
+}
+
+impl Provenance {
+    /// Builds the provenance of a block spanning `first_column..=last_column` in a row.
+    /// A block made of a single column is written as a cell reference (e.g., "F5").
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the Excel file.
+    /// * `sheet` - Name of the sheet.
+    /// * `row` - 1-based row number.
+    /// * `first_column` - 0-based index of the first column of the block.
+    /// * `last_column` - 0-based index of the last column of the block.
+    pub fn new(
+        file: &str,
+        sheet: &str,
+        row: usize,
+        first_column: usize,
+        last_column: usize,
+    ) -> Provenance {
+        Provenance {
+            file: file.to_string(),
+            sheet: sheet.to_string(),
+            row,
+            columns: if first_column == last_column {
+                format!("{}{}", column_letter(first_column), row)
+            } else {
+                format!(
+                    "{}{}:{}{}",
+                    column_letter(first_column),
+                    row,
+                    column_letter(last_column),
+                    row
+                )
+            },
+        }
+    }
+
+    /// Returns the provenance of the columns `first_offset..=last_offset`, counted from the
+    /// start of this location, in the same row.
+    pub fn span(&self, first_offset: usize, last_offset: usize) -> Provenance {
+        let letters: String = self
+            .columns
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        let first_column = column_index(&letters);
+        Provenance::new(
+            &self.file,
+            &self.sheet,
+            self.row,
+            first_column + first_offset,
+            first_column + last_offset,
+        )
+    }
+
+    /// Returns the provenance of the single cell `offset` columns after the start of this location.
+    pub fn cell(&self, offset: usize) -> Provenance {
+        self.span(offset, offset)
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'!{}", self.file, self.sheet, self.columns)
+    }
+}
+
+/// Converts a 0-based column index into its Excel letter (0 -> "A", 27 -> "AB").
+pub fn column_letter(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        letters.push((b'A' + rem as u8) as char);
+        n = (n - 1) / 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Converts an Excel column letter into its 0-based index ("A" -> 0, "AB" -> 27).
+pub fn column_index(letters: &str) -> usize {
+    letters
+        .chars()
+        .fold(0, |acc, c| {
+            acc * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)
+        })
+        .saturating_sub(1)
+}
@@ -0,0 +1,261 @@
+use super::phase::Phase;
+use crate::utils::post_request::CompressionStats;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Exit code of a run whose sheet had parse errors (dropped data), everything else sent.
+pub const EXIT_PARSE_ERRORS: u8 = 2;
+
+/// Exit code of a run where some activities were sent and others were not.
+pub const EXIT_PARTIAL_FAILURE: u8 = 3;
+
+/// Exit code of a run where no activity was sent.
+pub const EXIT_TOTAL_FAILURE: u8 = 4;
+
+/// What happened to an activity in a send run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// The server accepted the activity.
+    Sent,
+    /// The activity was not sent because its payload, route or contract was invalid.
+    Skipped,
+    /// The request failed or the server rejected the activity.
+    Failed,
+    /// The activity was not sent because the circuit breaker stopped sending.
+    Deferred,
+}
+
+/// Counts of a send run.
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+pub struct SendTotals {
+    /// Activities accepted by the server.
+    pub sent: usize,
+
+    /// Activities not sent because of invalid data.
+    pub skipped: usize,
+
+    /// Activities whose request failed.
+    pub failed: usize,
+
+    /// Activities deferred by the circuit breaker.
+    pub deferred: usize,
+
+    /// Requests sent again after a failed attempt.
+    pub retried: usize,
+}
+
+impl SendTotals {
+    fn add(&mut self, outcome: SendOutcome) {
+        match outcome {
+            SendOutcome::Sent => self.sent += 1,
+            SendOutcome::Skipped => self.skipped += 1,
+            SendOutcome::Failed => self.failed += 1,
+            SendOutcome::Deferred => self.deferred += 1,
+        }
+    }
+
+    /// Activities that were not sent, for any reason.
+    pub fn not_sent(&self) -> usize {
+        self.skipped + self.failed + self.deferred
+    }
+}
+
+impl fmt::Display for SendTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sent, {} skipped, {} failed, {} deferred, {} retried",
+            self.sent, self.skipped, self.failed, self.deferred, self.retried
+        )
+    }
+}
+
+/// Counts of one sheet, in total and per phase.
+#[derive(Debug, Serialize, Default)]
+pub struct SheetSummary {
+    /// Counts of every activity of the sheet.
+    #[serde(flatten)]
+    pub totals: SendTotals,
+
+    /// Counts of the activities of each phase, in session order.
+    pub phases: BTreeMap<Phase, SendTotals>,
+}
+
+/// Summary of a send run, printed at the end as text or JSON.
+#[derive(Debug, Serialize, Default)]
+pub struct RunSummary {
+    /// Counts of every activity of the run.
+    pub totals: SendTotals,
+
+    /// Counts of each sheet, keyed by sheet name.
+    pub sheets: BTreeMap<String, SheetSummary>,
+
+    /// Number of parse issues with error severity.
+    pub parse_errors: usize,
+
+    /// Blocks dropped for parse errors, also counted as skipped.
+    pub skipped_blocks: usize,
+
+    /// Whether the payloads were only printed, not sent.
+    pub dry_run: bool,
+
+    /// Duration of the run, in seconds.
+    pub duration_secs: f64,
+
+    /// Sizes of the request bodies sent compressed, when compression is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionStats>,
+}
+
+impl RunSummary {
+    /// Records the outcome of an activity.
+    pub fn record(&mut self, sheet: &str, phase: Phase, outcome: SendOutcome) {
+        let summary = self.sheets.entry(sheet.to_string()).or_default();
+        summary.phases.entry(phase).or_default().add(outcome);
+        summary.totals.add(outcome);
+        self.totals.add(outcome);
+    }
+
+    /// Records a phase block dropped for parse errors, which counts as a skipped activity.
+    pub fn record_skipped_block(&mut self, sheet: &str, phase: Phase) {
+        self.record(sheet, phase, SendOutcome::Skipped);
+        self.skipped_blocks += 1;
+    }
+
+    /// Records the retried requests of a sheet, and of one of its phases when the requests
+    /// only carried activities of that phase.
+    pub fn record_retries(&mut self, sheet: &str, phase: Option<Phase>, retries: usize) {
+        if retries == 0 {
+            return;
+        }
+        let summary = self.sheets.entry(sheet.to_string()).or_default();
+        if let Some(phase) = phase {
+            summary.phases.entry(phase).or_default().retried += retries;
+        }
+        summary.totals.retried += retries;
+        self.totals.retried += retries;
+    }
+
+    /// Exit code of the run: 0 when everything was sent, `EXIT_TOTAL_FAILURE` when nothing
+    /// was sent, `EXIT_PARTIAL_FAILURE` when only some activities were sent, and
+    /// `EXIT_PARSE_ERRORS` when everything was sent but the sheet had parse errors. Blocks
+    /// dropped for parse errors count as activities not sent. A dry run only depends on the
+    /// parse errors, as nothing is sent.
+    pub fn exit_code(&self) -> u8 {
+        match (self.totals.sent, self.totals.not_sent()) {
+            _ if self.dry_run => match self.parse_errors {
+                0 => 0,
+                _ => EXIT_PARSE_ERRORS,
+            },
+            (0, not_sent) if not_sent > 0 => EXIT_TOTAL_FAILURE,
+            (_, not_sent) if not_sent > 0 => EXIT_PARTIAL_FAILURE,
+            _ if self.parse_errors > 0 => EXIT_PARSE_ERRORS,
+            _ => 0,
+        }
+    }
+
+    /// Serializes the summary as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (sheet, summary) in &self.sheets {
+            writeln!(f, "{}: {}", sheet, summary.totals)?;
+            for (phase, totals) in &summary.phases {
+                writeln!(f, "  {}: {}", phase, totals)?;
+            }
+        }
+        if let Some(compression) = &self.compression {
+            writeln!(f, "{}", compression)?;
+        }
+        if self.dry_run {
+            writeln!(f, "Dry run, nothing was sent")?;
+        }
+        write!(
+            f,
+            "Total: {}, {} parse errors, in {:.1} s",
+            self.totals, self.parse_errors, self.duration_secs
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a summary of one sheet from the outcomes of its activities, the blocks
+    /// dropped for parse errors and the other parse errors.
+    fn summary(outcomes: &[SendOutcome], dropped_blocks: usize, parse_errors: usize) -> RunSummary {
+        let mut summary = RunSummary {
+            parse_errors: dropped_blocks + parse_errors,
+            ..RunSummary::default()
+        };
+        for _ in 0..dropped_blocks {
+            summary.record_skipped_block("1. DERECHA", Phase::WarmUp);
+        }
+        for outcome in outcomes {
+            summary.record("1. DERECHA", Phase::FinalPart, *outcome);
+        }
+        summary
+    }
+
+    #[test]
+    fn exit_codes() {
+        use SendOutcome::*;
+        let cases: [(&[SendOutcome], usize, usize, u8); 11] = [
+            (&[Sent, Sent], 0, 0, 0),
+            (&[], 0, 0, 0),
+            (&[Sent, Sent], 0, 1, EXIT_PARSE_ERRORS),
+            (&[Sent, Failed], 0, 0, EXIT_PARTIAL_FAILURE),
+            (&[Sent, Skipped], 0, 0, EXIT_PARTIAL_FAILURE),
+            (&[Sent, Deferred], 0, 0, EXIT_PARTIAL_FAILURE),
+            (&[Sent], 1, 0, EXIT_PARTIAL_FAILURE),
+            (&[Failed, Deferred], 0, 0, EXIT_TOTAL_FAILURE),
+            (&[Failed], 0, 1, EXIT_TOTAL_FAILURE),
+            (&[Skipped], 0, 0, EXIT_TOTAL_FAILURE),
+            (&[], 2, 0, EXIT_TOTAL_FAILURE),
+        ];
+        for (outcomes, dropped_blocks, parse_errors, expected) in cases {
+            assert_eq!(
+                summary(outcomes, dropped_blocks, parse_errors).exit_code(),
+                expected,
+                "outcomes {:?}, {} dropped blocks, {} other parse errors",
+                outcomes,
+                dropped_blocks,
+                parse_errors
+            );
+        }
+    }
+
+    #[test]
+    fn dry_runs_only_fail_on_parse_errors() {
+        use SendOutcome::*;
+        // The printed payloads are not sent, and invalid ones are skipped
+        let cases: [(&[SendOutcome], usize, usize, u8); 4] = [
+            (&[Skipped, Skipped], 0, 0, 0),
+            (&[], 0, 0, 0),
+            (&[Skipped], 1, 0, EXIT_PARSE_ERRORS),
+            (&[Skipped], 0, 1, EXIT_PARSE_ERRORS),
+        ];
+        for (outcomes, dropped_blocks, parse_errors, expected) in cases {
+            let mut summary = summary(outcomes, dropped_blocks, parse_errors);
+            summary.dry_run = true;
+            assert_eq!(summary.exit_code(), expected, "outcomes {:?}", outcomes);
+        }
+    }
+
+    #[test]
+    fn counts_dropped_blocks_as_skipped() {
+        let summary = summary(&[SendOutcome::Sent], 2, 0);
+        assert_eq!(summary.skipped_blocks, 2);
+        assert_eq!(summary.totals.skipped, 2);
+        assert_eq!(summary.totals.not_sent(), 2);
+        let sheet = &summary.sheets["1. DERECHA"];
+        assert_eq!(sheet.phases[&Phase::WarmUp].skipped, 2);
+        assert_eq!(sheet.phases[&Phase::FinalPart].sent, 1);
+    }
+}
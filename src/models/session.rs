@@ -0,0 +1,49 @@
+use super::activity::Actividad;
+use super::golpe::Golpe;
+use super::phase::Phase;
+use serde::{Deserialize, Serialize};
+
+/// A whole section sent to the API as a single document.
+///
+/// It holds the stroke of the section, its phases in session order with their
+/// activities, and totals computed from the activities.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionDocument {
+    /// Stroke programmed in the session, when it is in the golpe catalog.
+    pub golpe: Option<Golpe>,
+
+    /// Phases of the session, in session order.
+    pub phases: Vec<SessionPhase>,
+
+    /// Totals of the whole session.
+    pub totals: SessionTotals,
+}
+
+/// A phase of a session document.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionPhase {
+    /// The phase.
+    pub phase: Phase,
+
+    /// Sum of the durations of the activities of the phase, in minutes.
+    pub duration_minutes: u32,
+
+    /// Activities of the phase, in sheet order.
+    pub activities: Vec<Actividad>,
+}
+
+/// Totals computed over every activity of a session.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SessionTotals {
+    /// Number of activities.
+    pub activities: usize,
+
+    /// Sum of the durations of the activities, in minutes.
+    pub duration_minutes: u32,
+
+    /// Number of activities whose duration could not be read, left out of the sum.
+    pub unknown_durations: usize,
+
+    /// Equipment needed by any activity, sorted and without repetitions.
+    pub equipment: Vec<String>,
+}
@@ -0,0 +1,80 @@
+use super::parse_report::Severity;
+use super::provenance::Provenance;
+use serde::Serialize;
+use std::fmt;
+
+/// A rule violation found on an activity.
+#[derive(Debug, Serialize, Clone)]
+pub struct Finding {
+    /// Name of the rule that produced the finding (e.g., "unique_ids").
+    pub rule: String,
+
+    /// Severity configured for the rule.
+    pub severity: Severity,
+
+    /// ID of the activity, as read from the workbook.
+    pub activity: String,
+
+    /// Cell or block where the offending value is, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Provenance>,
+
+    /// Human readable description of the violation.
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {} '{}'", self.severity, self.rule, self.activity)?;
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Result of running the validation rules over one or more sections.
+#[derive(Debug, Serialize, Default)]
+pub struct ValidationReport {
+    /// Number of activities checked.
+    pub checked: usize,
+
+    /// Findings in the order they were produced.
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Number of findings with the given severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// Returns `true` if any finding has error severity.
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Serializes the report as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        write!(
+            f,
+            "{} activities checked: {} errors, {} warnings, {} notes",
+            self.checked,
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Info)
+        )
+    }
+}
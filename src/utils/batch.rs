@@ -0,0 +1,539 @@
+use super::openapi::OpenApiContract;
+use super::payload::{actividad_value, describe_actividad, PayloadOptions};
+use super::post_request::{ApiClient, HttpStatusError};
+use super::routing::Destination;
+use super::throttle::DeferredError;
+use crate::models::activity::Actividad;
+use crate::models::phase::Phase;
+use serde_json::Value;
+use std::error::Error;
+
+/// Default number of activities sent in each batch request.
+pub const DEFAULT_BATCH_SIZE: usize = 50;
+
+/// Activity serialized for a batch request.
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    /// Id of the activity in the workbook.
+    pub id: String,
+
+    /// Description of the activity for the logs, including its workbook location.
+    pub label: String,
+
+    /// Phase of the activity.
+    pub phase: Phase,
+
+    /// The payload of the activity.
+    pub payload: Value,
+}
+
+/// Outcome of one activity of a batch.
+#[derive(Debug, Clone)]
+pub enum ItemOutcome {
+    /// The server accepted the activity, with its per-item result when there is one.
+    Sent(Option<Value>),
+    /// The activity was rejected or could not be sent.
+    Failed(String),
+    /// The activity was not sent because the circuit breaker stopped sending.
+    Deferred(String),
+}
+
+/// Result of one activity of a batch.
+#[derive(Debug, Clone)]
+pub struct ItemResult {
+    /// Id of the activity in the workbook.
+    pub id: String,
+
+    /// Description of the activity for the logs.
+    pub label: String,
+
+    /// Phase of the activity.
+    pub phase: Phase,
+
+    /// Whether the activity was sent.
+    pub outcome: ItemOutcome,
+}
+
+/// Serializes an activity for batch requests.
+///
+/// # Arguments
+///
+/// * `actividad` - The activity to serialize.
+/// * `options` - The payload settings.
+/// * `schema` - The activity schema of `options`, built once with `payload_schema`.
+///
+/// # Returns
+///
+/// A `Result<BatchItem, Box<dyn Error>>` with the serialized activity, or an error if its
+/// payload cannot be built or does not match the payload schema.
+pub fn batch_item(
+    actividad: &Actividad,
+    options: &PayloadOptions,
+    schema: &Value,
+) -> Result<BatchItem, Box<dyn Error>> {
+    Ok(BatchItem {
+        id: actividad.id.clone(),
+        label: describe_actividad(actividad),
+        phase: actividad.phase,
+        payload: actividad_value(actividad, options, schema)?,
+    })
+}
+
+/// Sends activities to a bulk endpoint as JSON arrays of up to `batch_size` items.
+///
+/// The per-item results of each response are mapped back to the activities. When the
+/// server rejects the body of a whole batch (400, 413 or 422 status code), or the batch
+/// does not match the OpenAPI contract, the batch is split in half and each half is sent
+/// again, until the rejected activities are isolated. Any other error fails the whole
+/// batch at once.
+///
+/// # Arguments
+///
+/// * `client` - The HTTP client.
+/// * `items` - The serialized activities.
+/// * `destination` - The method and URL of the bulk endpoint.
+/// * `batch_size` - The maximum number of activities per request.
+/// * `contract` - The OpenAPI contract of the bulk endpoint, if any.
+///
+/// # Returns
+///
+/// A `Vec<ItemResult>` with one result per activity, in the same order as `items`.
+pub fn send_batches(
+    client: &ApiClient,
+    items: &[BatchItem],
+    destination: &Destination,
+    batch_size: usize,
+    contract: Option<&OpenApiContract>,
+) -> Vec<ItemResult> {
+    let mut results = Vec::new();
+    for batch in items.chunks(batch_size.max(1)) {
+        send_batch(client, batch, destination, contract, &mut results);
+    }
+    results
+}
+
+/// Builds the JSON body of a batch request.
+pub fn batch_body(items: &[BatchItem]) -> String {
+    Value::Array(items.iter().map(|item| item.payload.clone()).collect()).to_string()
+}
+
+fn send_batch(
+    client: &ApiClient,
+    items: &[BatchItem],
+    destination: &Destination,
+    contract: Option<&OpenApiContract>,
+    results: &mut Vec<ItemResult>,
+) {
+    let body = batch_body(items);
+
+    let violations = contract
+        .map(|contract| contract.check_request(&body))
+        .unwrap_or_default();
+    if !violations.is_empty() {
+        match items.len() {
+            1 => results.push(failed(
+                &items[0],
+                format!(
+                    "the request body does not match the OpenAPI contract: {}",
+                    violations.join("; ")
+                ),
+            )),
+            _ => split_batch(client, items, destination, contract, results),
+        }
+        return;
+    }
+
+    eprintln!("Sending batch of {} activities", items.len());
+    match client.send(destination.method.clone(), &body, &destination.url) {
+        Ok(response) => {
+            if let Some(contract) = contract {
+                for violation in contract.check_response(response.status, &response.body) {
+                    eprintln!(
+                        "Batch response does not match {}: {}",
+                        contract.operation, violation
+                    );
+                }
+            }
+            results.extend(item_results(items, &response.body));
+        }
+        Err(e) if e.is::<DeferredError>() => {
+            results.extend(
+                items
+                    .iter()
+                    .map(|item| item_result(item, ItemOutcome::Deferred(e.to_string()))),
+            );
+        }
+        Err(e) => {
+            let rejected = e
+                .downcast_ref::<HttpStatusError>()
+                .is_some_and(HttpStatusError::is_rejection);
+            if rejected && items.len() > 1 {
+                eprintln!(
+                    "Batch of {} activities rejected ({}), splitting it",
+                    items.len(),
+                    e
+                );
+                split_batch(client, items, destination, contract, results);
+            } else {
+                let message = match e.downcast_ref::<HttpStatusError>() {
+                    Some(error) if !error.body.is_empty() => format!("{}: {}", error, error.body),
+                    _ => e.to_string(),
+                };
+                results.extend(items.iter().map(|item| failed(item, message.clone())));
+            }
+        }
+    }
+}
+
+/// Sends each half of a batch on its own.
+fn split_batch(
+    client: &ApiClient,
+    items: &[BatchItem],
+    destination: &Destination,
+    contract: Option<&OpenApiContract>,
+    results: &mut Vec<ItemResult>,
+) {
+    let (first, second) = items.split_at(items.len() / 2);
+    send_batch(client, first, destination, contract, results);
+    send_batch(client, second, destination, contract, results);
+}
+
+/// Maps the per-item results of a batch response back to the activities.
+///
+/// The results are read from a JSON array, either the body itself or its "results",
+/// "items" or "data" field. Each result is matched to an activity by its
+/// "activity_id" or "activityId" field when it is the id of an activity of the batch,
+/// and by position otherwise. The generic "id" field is only read when no result has
+/// those fields, as it is usually the id given by the server. A result never replaces
+/// the one already matched to an activity. A result is a failure when its "status" is an
+/// error code or "error"/"failed", when its "ok" or "success" field is false, or when
+/// it has an "error" or "errors" field. Without per-item results, every activity of
+/// the batch is considered sent.
+fn item_results(items: &[BatchItem], body: &str) -> Vec<ItemResult> {
+    let parsed: Option<Value> = serde_json::from_str(body).ok();
+    let entries = parsed.as_ref().and_then(|value| match value {
+        Value::Array(entries) => Some(entries),
+        Value::Object(object) => ["results", "items", "data"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(Value::as_array)),
+        _ => None,
+    });
+    let Some(entries) = entries else {
+        return items
+            .iter()
+            .map(|item| item_result(item, ItemOutcome::Sent(None)))
+            .collect();
+    };
+
+    let activity_keys = ["activity_id", "activityId"];
+    let keys: &[&str] = match entries
+        .iter()
+        .any(|entry| activity_keys.iter().any(|key| entry.get(*key).is_some()))
+    {
+        true => &activity_keys,
+        false => &["id"],
+    };
+
+    // Results with the id of an activity first, so that the ones matched by position
+    // only fill the activities left
+    let mut outcomes: Vec<Option<ItemOutcome>> = vec![None; items.len()];
+    let mut unmatched = Vec::new();
+    for (position, entry) in entries.iter().enumerate() {
+        let index = keys
+            .iter()
+            .filter_map(|key| entry.get(*key).and_then(Value::as_str))
+            .find_map(|id| items.iter().position(|item| item.id == id));
+        match index {
+            Some(index) => {
+                if outcomes[index].is_none() {
+                    outcomes[index] = Some(entry_outcome(entry));
+                }
+            }
+            None => unmatched.push((position, entry)),
+        }
+    }
+    for (position, entry) in unmatched {
+        if let Some(outcome) = outcomes
+            .get_mut(position)
+            .filter(|outcome| outcome.is_none())
+        {
+            *outcome = Some(entry_outcome(entry));
+        }
+    }
+
+    items
+        .iter()
+        .zip(outcomes)
+        .map(|(item, outcome)| {
+            item_result(
+                item,
+                outcome.unwrap_or_else(|| {
+                    ItemOutcome::Failed("missing from the batch response".to_string())
+                }),
+            )
+        })
+        .collect()
+}
+
+/// Reads whether a per-item result is a success.
+fn entry_outcome(entry: &Value) -> ItemOutcome {
+    let error = ["error", "errors", "message"]
+        .iter()
+        .filter_map(|key| entry.get(*key))
+        .find(|value| !value.is_null())
+        .map(|value| match value {
+            Value::String(message) => message.clone(),
+            Value::Object(object) => object
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string()),
+            other => other.to_string(),
+        });
+
+    let failed = match entry.get("status") {
+        Some(Value::Number(status)) => status.as_u64().is_some_and(|status| status >= 300),
+        Some(Value::String(status)) => {
+            matches!(
+                status.to_lowercase().as_str(),
+                "error" | "failed" | "rejected"
+            )
+        }
+        _ => {
+            ["ok", "success"]
+                .iter()
+                .filter_map(|key| entry.get(*key).and_then(Value::as_bool))
+                .any(|ok| !ok)
+                || entry.get("error").is_some_and(|value| !value.is_null())
+                || entry.get("errors").is_some_and(|value| !value.is_null())
+        }
+    };
+
+    match failed {
+        true => ItemOutcome::Failed(error.unwrap_or_else(|| "rejected by the server".to_string())),
+        false => ItemOutcome::Sent(Some(entry.clone())),
+    }
+}
+
+fn failed(item: &BatchItem, message: String) -> ItemResult {
+    item_result(item, ItemOutcome::Failed(message))
+}
+
+fn item_result(item: &BatchItem, outcome: ItemOutcome) -> ItemResult {
+    ItemResult {
+        id: item.id.clone(),
+        label: item.label.clone(),
+        phase: item.phase,
+        outcome,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Method;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    fn item(id: &str) -> BatchItem {
+        BatchItem {
+            id: id.to_string(),
+            label: format!("activity {}", id),
+            phase: Phase::WarmUp,
+            payload: json!({ "id": id }),
+        }
+    }
+
+    fn batch(count: usize) -> Vec<BatchItem> {
+        (1..=count).map(|id| item(&id.to_string())).collect()
+    }
+
+    /// Outcomes as "sent", "failed" or "deferred", in the order of the results.
+    fn outcomes(results: &[ItemResult]) -> Vec<(&str, &str)> {
+        results
+            .iter()
+            .map(|result| {
+                let outcome = match result.outcome {
+                    ItemOutcome::Sent(_) => "sent",
+                    ItemOutcome::Failed(_) => "failed",
+                    ItemOutcome::Deferred(_) => "deferred",
+                };
+                (result.id.as_str(), outcome)
+            })
+            .collect()
+    }
+
+    /// Starts an HTTP server on a local port that answers each request with the status
+    /// and body returned by `answer` for the ids of its batch, and records those ids.
+    fn serve(
+        answer: impl Fn(&[String]) -> (u16, String) + Send + 'static,
+    ) -> (Destination, Arc<Mutex<Vec<Vec<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/activities/bulk", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&mut stream);
+                let mut length = 0;
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    line.clear();
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let payloads: Vec<Value> = serde_json::from_slice(&body).unwrap();
+                let ids: Vec<String> = payloads
+                    .iter()
+                    .map(|payload| payload["id"].as_str().unwrap().to_string())
+                    .collect();
+                let (status, body) = answer(&ids);
+                recorded.lock().unwrap().push(ids);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        let destination = Destination {
+            method: Method::POST,
+            url,
+        };
+        (destination, requests)
+    }
+
+    fn send(items: &[BatchItem], destination: &Destination) -> Vec<ItemResult> {
+        let client = ApiClient::new(Default::default()).unwrap();
+        send_batches(&client, items, destination, DEFAULT_BATCH_SIZE, None)
+    }
+
+    #[test]
+    fn halves_the_batches_the_server_rejects() {
+        for status in [400, 413, 422] {
+            // Activity 3 is invalid, so every batch containing it is rejected
+            let (destination, requests) = serve(move |ids| match ids.iter().any(|id| id == "3") {
+                true => (status, String::new()),
+                false => (200, String::new()),
+            });
+            let results = send(&batch(4), &destination);
+            assert_eq!(
+                outcomes(&results),
+                [("1", "sent"), ("2", "sent"), ("3", "failed"), ("4", "sent")],
+                "status {}",
+                status
+            );
+            let sizes: Vec<usize> = requests.lock().unwrap().iter().map(Vec::len).collect();
+            assert_eq!(sizes, [4, 2, 2, 1, 1], "status {}", status);
+        }
+    }
+
+    #[test]
+    fn fails_the_whole_batch_on_other_errors() {
+        for status in [401, 404] {
+            let (destination, requests) = serve(move |_| (status, String::new()));
+            let results = send(&batch(4), &destination);
+            assert!(outcomes(&results)
+                .iter()
+                .all(|(_, outcome)| *outcome == "failed"));
+            assert_eq!(requests.lock().unwrap().len(), 1, "status {}", status);
+        }
+    }
+
+    #[test]
+    fn reads_the_results_from_the_usual_envelopes() {
+        let entries = json!([{ "ok": true }, { "ok": false, "error": "invalid" }]);
+        for body in [
+            entries.clone(),
+            json!({ "results": entries }),
+            json!({ "items": entries }),
+            json!({ "data": entries }),
+        ] {
+            let results = item_results(&batch(2), &body.to_string());
+            assert_eq!(
+                outcomes(&results),
+                [("1", "sent"), ("2", "failed")],
+                "{}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn considers_every_activity_sent_without_results() {
+        for body in ["", r#"{"created": 2}"#] {
+            let results = item_results(&batch(2), body);
+            assert_eq!(outcomes(&results), [("1", "sent"), ("2", "sent")]);
+        }
+    }
+
+    #[test]
+    fn matches_the_results_by_activity_id() {
+        let body = json!([
+            { "activity_id": "2", "status": "error" },
+            { "activityId": "1", "status": 201 },
+        ]);
+        let results = item_results(&batch(2), &body.to_string());
+        assert_eq!(outcomes(&results), [("1", "sent"), ("2", "failed")]);
+    }
+
+    #[test]
+    fn ignores_the_server_id_when_results_have_activity_ids() {
+        // The "id" of the first result is the server id, which happens to look like the
+        // id of activity 2
+        let body = json!([
+            { "id": "2", "activity_id": "1", "status": 201 },
+            { "id": "7", "activity_id": "2", "status": 422 },
+        ]);
+        let results = item_results(&batch(2), &body.to_string());
+        assert_eq!(outcomes(&results), [("1", "sent"), ("2", "failed")]);
+    }
+
+    #[test]
+    fn matches_the_results_by_id_or_position() {
+        let body = json!([{ "id": "2", "status": 422 }, { "id": "1", "status": 201 }]);
+        let results = item_results(&batch(2), &body.to_string());
+        assert_eq!(outcomes(&results), [("1", "sent"), ("2", "failed")]);
+
+        let body = json!([{ "status": 201 }, { "status": "failed" }]);
+        let results = item_results(&batch(2), &body.to_string());
+        assert_eq!(outcomes(&results), [("1", "sent"), ("2", "failed")]);
+    }
+
+    #[test]
+    fn does_not_replace_a_matched_result() {
+        // The first result is at the position of activity 1, which the second result
+        // claims by id; the third result claims it again
+        let body = json!([
+            { "status": "failed" },
+            { "activity_id": "1", "status": 201 },
+            { "activity_id": "1", "status": "failed" },
+        ]);
+        let results = item_results(&batch(2), &body.to_string());
+        assert_eq!(outcomes(&results), [("1", "sent"), ("2", "failed")]);
+        assert!(
+            matches!(&results[1].outcome, ItemOutcome::Failed(message) if message == "missing from the batch response")
+        );
+    }
+
+    #[test]
+    fn fails_the_activities_missing_from_the_response() {
+        let body = json!([{ "status": 201 }]);
+        let results = item_results(&batch(3), &body.to_string());
+        assert_eq!(
+            outcomes(&results),
+            [("1", "sent"), ("2", "failed"), ("3", "failed")]
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use super::batch::DEFAULT_BATCH_SIZE;
+use super::export::ExportFormat;
+use super::payload::PayloadVersion;
+use super::profiles::{Profile, DEFAULT_PROFILES_PATH};
+use super::schema::SchemaDocument;
+use super::state::ResponseFields;
+use crate::models::phase::PhaseFormat;
+use std::error::Error;
+use std::str::FromStr;
+
+/// Default Excel file read by the send command.
+pub const DEFAULT_PATH: &str = "programming-table-2.xlsx";
+
+/// Default sheet read by the send command.
+pub const DEFAULT_SHEET: &str = "1. DERECHA PLANA";
+
+/// Output format of the reports printed by the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Human readable text.
+    Text,
+    /// Pretty-printed JSON.
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            other => Err(format!("Unknown report format: {}", other)),
+        }
+    }
+}
+
+/// How the send command posts the activities of a sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendMode {
+    /// One request per activity.
+    Activities,
+    /// One request with the session document of the whole sheet.
+    Session,
+    /// Requests with arrays of activities sent to a bulk endpoint.
+    Batch,
+}
+
+impl FromStr for SendMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "activities" => Ok(SendMode::Activities),
+            "session" => Ok(SendMode::Session),
+            "batch" => Ok(SendMode::Batch),
+            other => Err(format!("Unknown send mode: {}", other)),
+        }
+    }
+}
+
+/// Action requested on the command line.
+#[derive(Debug)]
+pub enum Command {
+    /// Load the activities from the workbook and send them to the API.
+    Send,
+    /// Download the activities from a listing endpoint and export them.
+    Pull {
+        endpoint: String,
+        format: ExportFormat,
+        output: String,
+    },
+    /// Load the activities from the workbook and check them against the validation rules.
+    Validate {
+        /// JSON file with the rules, the built-in rules are used when missing.
+        rules: Option<String>,
+        /// Validate only the selected sheet instead of every sheet of the workbook.
+        single_sheet: bool,
+    },
+    /// Print or write the JSON Schema of the payload.
+    Schema {
+        /// The document described by the schema.
+        document: SchemaDocument,
+        /// File where the schema is written, printed when missing.
+        output: Option<String>,
+    },
+}
+
+/// Arguments accepted by the program.
+#[derive(Debug)]
+pub struct CliArgs {
+    /// The action to run.
+    pub command: Command,
+
+    /// Path of the Excel file to read.
+    pub path: String,
+
+    /// Name of the sheet to read.
+    pub sheet: String,
+
+    /// Name of the environment profile to use.
+    pub profile: Option<String>,
+
+    /// JSON file with the environment profiles.
+    pub profiles_path: String,
+
+    /// Confirms sending to a protected profile.
+    pub confirm_protected: bool,
+
+    /// Whether to include the workbook location of each activity in the payload.
+    pub include_provenance: bool,
+
+    /// How the phase of each activity is written in the payload.
+    pub phase_format: PhaseFormat,
+
+    /// Version of the payload contract.
+    pub payload_version: PayloadVersion,
+
+    /// URL template the send command posts the activities to.
+    pub endpoint: Option<String>,
+
+    /// JSON routing table mapping phases and sheets to endpoints.
+    pub routes: Option<String>,
+
+    /// Value of the `{base}` placeholder of the URL templates.
+    pub base_url: Option<String>,
+
+    /// JSON OpenAPI specification the request and response bodies are checked against.
+    pub openapi: Option<String>,
+
+    /// Whether activities are sent one by one or as a single session document.
+    pub send_mode: SendMode,
+
+    /// URL of the bulk endpoint used in batch mode, `endpoint` when missing.
+    pub bulk_endpoint: Option<String>,
+
+    /// Maximum number of activities per request in batch mode.
+    pub batch_size: usize,
+
+    /// JSON file where the server id and response fields of each activity are stored.
+    pub state: Option<String>,
+
+    /// Dotted path of the server id in the responses.
+    pub server_id_field: String,
+
+    /// Dotted paths of the other response fields stored in the state file.
+    pub response_fields: Vec<String>,
+
+    /// JSON file mapping the activity fields onto the schema of the API.
+    pub mapping: Option<String>,
+
+    /// Print the payloads instead of sending them.
+    pub dry_run: bool,
+
+    /// File where the parse report is written as JSON.
+    pub parse_report: Option<String>,
+
+    /// Format of the reports printed on the standard output.
+    pub report_format: ReportFormat,
+
+    /// JSON file describing the sheet layout, the default layout is used when missing.
+    pub layout: Option<String>,
+
+    /// JSON translation memory used to fill empty translations.
+    pub translation_memory: Option<String>,
+
+    /// File where the texts that still need a human translation are listed as JSON.
+    pub pending_translations: Option<String>,
+
+    /// Whether accents are ignored when matching the terms of the sheet.
+    pub fold_accents: bool,
+
+    /// Confidence from which unknown terms are replaced by the closest known term.
+    pub autocorrect: Option<f64>,
+
+    /// JSON golpe catalog, the built-in catalog is used when missing.
+    pub golpes: Option<String>,
+}
+
+/// Parses the command line arguments.
+///
+/// Usage:
+///
+/// ```text
+/// excel_reader [send] [--file PATH] [--sheet NAME] [PARSE OPTIONS] [--endpoint URL] [--routes PATH]
+///                      [--base-url URL] [--openapi PATH]
+///                      [--include-provenance] [--phase-format legacy|indexed|object]
+///                      [--payload-version v1|v2] [--send-mode activities|session|batch] [--bulk-endpoint URL]
+///                      [--batch-size N] [--state PATH] [--server-id-field PATH] [--response-fields A,B]
+///                      [--mapping PATH] [--dry-run]
+///                      [--parse-report PATH] [--report-format text|json]
+/// excel_reader pull ENDPOINT [--format json|jsonl|excel] [--output PATH] [--layout PATH] [--golpes PATH]
+/// excel_reader validate [--file PATH] [--sheet NAME] [PARSE OPTIONS] [--rules PATH] [--report-format text|json]
+/// excel_reader schema [--document activity|session] [--payload-version v1|v2] [--phase-format legacy|indexed|object]
+///                     [--output PATH]
+///
+/// Every command also accepts [--profile NAME] [--profiles PATH] [--confirm-protected].
+///
+/// PARSE OPTIONS: [--layout PATH] [--translation-memory PATH] [--pending-translations PATH]
+///                [--no-accent-folding] [--autocorrect THRESHOLD] [--golpes PATH]
+/// ```
+///
+/// # Arguments
+///
+/// * `args` - The arguments, without the program name.
+///
+/// # Returns
+///
+/// A `Result<CliArgs, Box<dyn Error>>` with the parsed arguments, or an error describing the invalid argument.
+pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<CliArgs, Box<dyn Error>> {
+    let mut args = args.peekable();
+    let command_name = match args.peek() {
+        Some(arg) if !arg.starts_with("--") => args.next().unwrap_or_default(),
+        _ => "send".to_string(),
+    };
+
+    let mut path = DEFAULT_PATH.to_string();
+    let mut sheet = DEFAULT_SHEET.to_string();
+    let mut single_sheet = false;
+    let mut profile = None;
+    let mut profiles_path = DEFAULT_PROFILES_PATH.to_string();
+    let mut confirm_protected = false;
+    let mut endpoint = None;
+    let mut format = ExportFormat::Json;
+    let mut output = None;
+    let mut include_provenance = false;
+    let mut phase_format = PhaseFormat::default();
+    let mut payload_version = PayloadVersion::default();
+    let mut document = SchemaDocument::Activity;
+    let mut openapi = None;
+    let mut routes = None;
+    let mut base_url = None;
+    let mut send_mode = SendMode::Activities;
+    let mut bulk_endpoint = None;
+    let mut batch_size = DEFAULT_BATCH_SIZE;
+    let mut state = None;
+    let mut response_fields = ResponseFields::default();
+    let mut mapping = None;
+    let mut dry_run = false;
+    let mut parse_report = None;
+    let mut rules = None;
+    let mut report_format = ReportFormat::Text;
+    let mut layout = None;
+    let mut translation_memory = None;
+    let mut pending_translations = None;
+    let mut fold_accents = true;
+    let mut autocorrect = None;
+    let mut golpes = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--file" => path = expect_value(&mut args, &arg)?,
+            "--sheet" => {
+                sheet = expect_value(&mut args, &arg)?;
+                single_sheet = true;
+            }
+            "--profile" => profile = Some(expect_value(&mut args, &arg)?),
+            "--profiles" => profiles_path = expect_value(&mut args, &arg)?,
+            "--confirm-protected" => confirm_protected = true,
+            "--format" => format = expect_value(&mut args, &arg)?.parse()?,
+            "--output" => output = Some(expect_value(&mut args, &arg)?),
+            "--include-provenance" => include_provenance = true,
+            "--phase-format" => phase_format = expect_value(&mut args, &arg)?.parse()?,
+            "--payload-version" => payload_version = expect_value(&mut args, &arg)?.parse()?,
+            "--document" => document = expect_value(&mut args, &arg)?.parse()?,
+            "--endpoint" => endpoint = Some(expect_value(&mut args, &arg)?),
+            "--routes" => routes = Some(expect_value(&mut args, &arg)?),
+            "--base-url" => base_url = Some(expect_value(&mut args, &arg)?),
+            "--openapi" => openapi = Some(expect_value(&mut args, &arg)?),
+            "--send-mode" => send_mode = expect_value(&mut args, &arg)?.parse()?,
+            "--bulk-endpoint" => bulk_endpoint = Some(expect_value(&mut args, &arg)?),
+            "--batch-size" => {
+                batch_size = expect_value(&mut args, &arg)?.parse()?;
+                if batch_size == 0 {
+                    return Err("The batch size must be greater than 0.".into());
+                }
+            }
+            "--state" => state = Some(expect_value(&mut args, &arg)?),
+            "--server-id-field" => response_fields.server_id = expect_value(&mut args, &arg)?,
+            "--response-fields" => {
+                response_fields.fields = expect_value(&mut args, &arg)?
+                    .split(',')
+                    .map(|field| field.trim().to_string())
+                    .filter(|field| !field.is_empty())
+                    .collect()
+            }
+            "--mapping" => mapping = Some(expect_value(&mut args, &arg)?),
+            "--dry-run" => dry_run = true,
+            "--parse-report" => parse_report = Some(expect_value(&mut args, &arg)?),
+            "--rules" => rules = Some(expect_value(&mut args, &arg)?),
+            "--report-format" => report_format = expect_value(&mut args, &arg)?.parse()?,
+            "--layout" => layout = Some(expect_value(&mut args, &arg)?),
+            "--translation-memory" => translation_memory = Some(expect_value(&mut args, &arg)?),
+            "--pending-translations" => pending_translations = Some(expect_value(&mut args, &arg)?),
+            "--no-accent-folding" => fold_accents = false,
+            "--golpes" => golpes = Some(expect_value(&mut args, &arg)?),
+            "--autocorrect" => {
+                let threshold: f64 = expect_value(&mut args, &arg)?.parse()?;
+                if !(0.0..=1.0).contains(&threshold) {
+                    return Err("The autocorrect threshold must be between 0 and 1.".into());
+                }
+                autocorrect = Some(threshold);
+            }
+            value if !value.starts_with("--") && endpoint.is_none() => {
+                endpoint = Some(value.to_string())
+            }
+            other => return Err(format!("Unknown argument: {}", other).into()),
+        }
+    }
+
+    let send_endpoint = endpoint.clone();
+    let command = match command_name.as_str() {
+        "send" => Command::Send,
+        "pull" => Command::Pull {
+            endpoint: endpoint.ok_or("The pull command needs a listing endpoint.")?,
+            output: output.unwrap_or_else(|| default_output(format).to_string()),
+            format,
+        },
+        "validate" => Command::Validate {
+            rules,
+            single_sheet,
+        },
+        "schema" => Command::Schema { document, output },
+        other => return Err(format!("Unknown command: {}", other).into()),
+    };
+
+    Ok(CliArgs {
+        command,
+        path,
+        sheet,
+        profile,
+        profiles_path,
+        confirm_protected,
+        include_provenance,
+        phase_format,
+        payload_version,
+        endpoint: send_endpoint,
+        routes,
+        base_url,
+        openapi,
+        send_mode,
+        bulk_endpoint,
+        batch_size,
+        state,
+        server_id_field: response_fields.server_id,
+        response_fields: response_fields.fields,
+        mapping,
+        dry_run,
+        parse_report,
+        report_format,
+        layout,
+        translation_memory,
+        pending_translations,
+        fold_accents,
+        autocorrect,
+        golpes,
+    })
+}
+
+impl CliArgs {
+    /// Fills the settings missing from the command line with the values of a profile, and
+    /// places the relative output files in the output directory of the profile.
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        if self.endpoint.is_none() {
+            self.endpoint = profile.endpoint.clone();
+        }
+        if self.base_url.is_none() {
+            self.base_url = profile.base_url.clone();
+        }
+        for output in [
+            &mut self.parse_report,
+            &mut self.pending_translations,
+            &mut self.state,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *output = profile.output_path(output);
+        }
+        match &mut self.command {
+            Command::Pull { output, .. } => *output = profile.output_path(output),
+            Command::Schema {
+                output: Some(output),
+                ..
+            } => *output = profile.output_path(output),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the value following a flag, or an error if it is missing.
+fn expect_value<I: Iterator<Item = String>>(
+    args: &mut I,
+    flag: &str,
+) -> Result<String, Box<dyn Error>> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for {}", flag).into())
+}
+
+/// Default output file for each export format.
+fn default_output(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Json => "actividades.json",
+        ExportFormat::Jsonl => "actividades.jsonl",
+        ExportFormat::Excel => "actividades.xlsx",
+    }
+}
@@ -1,151 +1,645 @@
+use super::golpes::{golpe_from_sheet_name, GolpeCatalog};
+use super::layout::SheetLayout;
 use super::parser::{
     process_duration, process_golpe, process_num_jugadores, split_to_vec,
     translate_level_to_english, translate_material, translate_model_to_english,
     translate_part_to_practice, translate_shot_to_english, translate_typology_to_english,
+    Translation,
+};
+use super::translation_memory::TranslationMemory;
+use super::vocabulary::VocabularyOptions;
+use crate::models::{
+    activity::Actividad,
+    activity_sections::Seccion,
+    content::Content,
+    golpe::Golpe,
+    parse_report::{IssueKind, ParseReport, Severity},
+    phase::Phase,
+    provenance::Provenance,
 };
-use crate::models::{activity::Actividad, activity_sections::Seccion, content::Content};
 use calamine::{open_workbook, DataType, Reader, Xlsx};
 use std::collections::HashMap;
 use std::error::Error;
 
+/// Settings that control how a sheet is parsed.
+#[derive(Debug, Default)]
+pub struct ParseOptions {
+    /// Layout of the sheet.
+    pub layout: SheetLayout,
+
+    /// Translation memory used to fill empty content in the secondary languages.
+    pub translation_memory: Option<TranslationMemory>,
+
+    /// How the terms of the sheet are matched against the translation tables.
+    pub vocabulary: VocabularyOptions,
+
+    /// Strokes that can be programmed, used to read the golpe from the sheet name.
+    pub golpes: GolpeCatalog,
+}
+
+/// Returns the column offset of an activity field from the start of its phase block.
+///
+/// Fields read from the columns shared by the whole row (golpe, num_jugadores,
+/// typology and level) are not part of the block and return `None`. Content columns
+/// depend on the language and are described by `SheetLayout::content_offset`.
+pub fn block_field_offset(field: &str) -> Option<usize> {
+    match field {
+        "id" => Some(0),
+        "model" => Some(1),
+        "shot" => Some(2),
+        "part_to_practice" => Some(3),
+        "equipment" => Some(4),
+        "duration" => Some(5),
+        _ => None,
+    }
+}
+
+/// Lists the sheets of an Excel file, in workbook order.
+///
+/// # Arguments
+///
+/// * `path` - The file path to the Excel file.
+///
+/// # Returns
+///
+/// A `Result<Vec<String>, Box<dyn Error>>` with the sheet names.
+pub fn sheet_names(path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let workbook: Xlsx<_> = open_workbook(path)?;
+    Ok(workbook.sheet_names().to_vec())
+}
+
 /// Extracts content information from a row in an Excel sheet and organizes it
 /// into a `HashMap` keyed by language codes (e.g., "ES" for Spanish).
 ///
+/// The primary language of the layout is always present. Other languages are only
+/// added when at least one of their columns is filled.
+///
 /// # Arguments
 ///
 /// * `row` - A slice of `DataType` representing a row of data from the Excel sheet.
-/// * `start_index` - The starting index for extracting content in the given row.
+/// * `start_index` - The starting index of the activity block in the given row.
+/// * `layout` - The layout declaring the content columns of each language.
 ///
 /// # Returns
 ///
 /// A `HashMap<String, Content>` containing the content for different languages.
-pub fn create_content(row: &[DataType], start_index: usize) -> HashMap<String, Content> {
+pub fn create_content(
+    row: &[DataType],
+    start_index: usize,
+    layout: &SheetLayout,
+) -> HashMap<String, Content> {
     let mut content = HashMap::new();
-    if row.len() > start_index + 2 {
+    for language in &layout.languages {
+        let index = start_index + language.offset;
+        if row.len() <= index + 2 {
+            continue;
+        }
+        let cells = &row[index..index + 3];
+        if language.code != layout.primary_language() && cells.iter().all(is_blank) {
+            continue;
+        }
         content.insert(
-            "ES".to_string(),
+            language.code.clone(),
             Content {
-                title: row[start_index].to_string(),
-                goal: row[start_index + 1].to_string(),
-                script: row[start_index + 2].to_string(),
+                title: cells[0].to_string(),
+                goal: cells[1].to_string(),
+                script: cells[2].to_string(),
+                machine_filled: false,
             },
         );
     }
-    // Additional languages can be added here.
     content
 }
 
+/// Lists the content fields of a language that are empty or missing.
+pub fn missing_content_fields(
+    content: &HashMap<String, Content>,
+    language: &str,
+) -> Vec<&'static str> {
+    let translated = content.get(language);
+    REQUIRED_CONTENT_FIELDS
+        .into_iter()
+        .filter(|field| {
+            let value = translated.map(|c| match *field {
+                "title" => c.title.as_str(),
+                "goal" => c.goal.as_str(),
+                _ => c.script.as_str(),
+            });
+            value.is_none_or(|v| v.trim().is_empty())
+        })
+        .collect()
+}
+
+/// Fields an activity block must fill, with their column offset from the block start.
+const REQUIRED_BLOCK_FIELDS: [(&str, usize); 5] = [
+    ("id", 0),
+    ("model", 1),
+    ("shot", 2),
+    ("part_to_practice", 3),
+    ("duration", 5),
+];
+
+/// Content fields an activity block must fill in the primary language.
+const REQUIRED_CONTENT_FIELDS: [&str; 3] = ["title", "goal", "script"];
+
+/// Lists the required fields of an activity block that are empty.
+///
+/// # Arguments
+///
+/// * `row` - A slice of `DataType` representing a row of data from the Excel sheet.
+/// * `start_index` - The starting index of the activity block.
+/// * `content` - The content extracted for the block.
+/// * `primary_language` - The language every activity must fill.
+///
+/// # Returns
+///
+/// A `Vec<&str>` with the names of the missing fields, empty if the block is complete.
+fn missing_fields(
+    row: &[DataType],
+    start_index: usize,
+    content: &HashMap<String, Content>,
+    primary_language: &str,
+) -> Vec<&'static str> {
+    let mut missing: Vec<&'static str> = REQUIRED_BLOCK_FIELDS
+        .iter()
+        .filter(|(_, offset)| is_blank(&row[start_index + offset]))
+        .map(|(field, _)| *field)
+        .collect();
+    missing.extend(missing_content_fields(content, primary_language));
+    missing
+}
+
+/// Returns `true` if a cell has no value or only whitespace.
+fn is_blank(cell: &DataType) -> bool {
+    cell.to_string().trim().is_empty()
+}
+
+/// Splits a multi-value cell with the separators of its field.
+fn split_cell(cell: &DataType, field: &str, layout: &SheetLayout) -> Vec<String> {
+    split_to_vec(
+        &cell.to_string().to_uppercase(),
+        layout.separators(field),
+        layout.dedupe_lists,
+    )
+}
+
+/// Records a parse issue for each term that could not be translated or was auto-corrected.
+fn report_untranslated(
+    report: &mut ParseReport,
+    translation: &Translation,
+    location: Provenance,
+    field: &str,
+) {
+    for (term, suggestion) in &translation.untranslated {
+        let message = match suggestion {
+            Some(suggestion) => format!(
+                "no translation for '{}', did you mean '{}'? (confidence {:.2})",
+                term, suggestion.term, suggestion.confidence
+            ),
+            None => format!("no translation for '{}'", term),
+        };
+        report.push(
+            IssueKind::UntranslatedTerm,
+            Severity::Warning,
+            location.clone(),
+            Some(field),
+            message,
+        );
+    }
+    for (term, suggestion) in &translation.corrected {
+        report.push(
+            IssueKind::CorrectedTerm,
+            Severity::Warning,
+            location.clone(),
+            Some(field),
+            format!(
+                "'{}' read as '{}' (confidence {:.2})",
+                term, suggestion.term, suggestion.confidence
+            ),
+        );
+    }
+}
+
+/// Finds the stroke programmed in a sheet from its name (e.g., "1. DERECHA PLANA").
+///
+/// The id and the name of the sheet are checked against the golpe catalog; any
+/// disagreement is recorded in the report.
+///
+/// # Arguments
+///
+/// * `sheet_name` - The name of the sheet.
+/// * `location` - The first cell of the sheet, used to locate the issues.
+/// * `catalog` - The golpe catalog.
+/// * `report` - The parse report.
+///
+/// # Returns
+///
+/// An `Option<&Golpe>` with the stroke of the sheet, or `None` if it cannot be told.
+fn golpe_of_sheet<'a>(
+    sheet_name: &str,
+    location: Provenance,
+    catalog: &'a GolpeCatalog,
+    report: &mut ParseReport,
+) -> Option<&'a Golpe> {
+    let Some((id, name)) = golpe_from_sheet_name(sheet_name) else {
+        return catalog.find_by_name(sheet_name);
+    };
+    let Some(golpe) = catalog.find(id) else {
+        report.push(
+            IssueKind::UnknownGolpe,
+            Severity::Warning,
+            location,
+            Some("golpe"),
+            format!("golpe {} of the sheet name is not in the golpe catalog", id),
+        );
+        return catalog.find_by_name(&name);
+    };
+    if catalog
+        .find_by_name(&name)
+        .is_some_and(|named| named.id != golpe.id)
+    {
+        report.push(
+            IssueKind::GolpeMismatch,
+            Severity::Warning,
+            location,
+            Some("golpe"),
+            format!(
+                "the sheet name says '{}' but golpe {} is '{}' in the catalog, using {}",
+                name, id, golpe.name_es, id
+            ),
+        );
+    }
+    Some(golpe)
+}
+
+/// Reads the golpe of a row from column A, falling back to the golpe of the sheet.
+///
+/// # Arguments
+///
+/// * `cell` - The golpe cell of the row.
+/// * `location` - The location of the cell.
+/// * `sheet_golpe` - The stroke of the sheet, as returned by `golpe_of_sheet`.
+/// * `catalog` - The golpe catalog.
+/// * `report` - The parse report.
+///
+/// # Returns
+///
+/// The golpe id, or 0 if neither the cell nor the sheet name tell it.
+fn resolve_golpe(
+    cell: &DataType,
+    location: Provenance,
+    sheet_golpe: Option<&Golpe>,
+    catalog: &GolpeCatalog,
+    report: &mut ParseReport,
+) -> i32 {
+    match (process_golpe(&cell.to_string()), sheet_golpe) {
+        (Some(id), Some(sheet)) if id != sheet.id => {
+            report.push(
+                IssueKind::GolpeMismatch,
+                Severity::Warning,
+                location,
+                Some("golpe"),
+                format!(
+                    "column A says golpe {} but the sheet is golpe {} ({}), using {}",
+                    id, sheet.id, sheet.name_es, id
+                ),
+            );
+            id
+        }
+        (Some(id), _) => {
+            if catalog.find(id).is_none() {
+                report.push(
+                    IssueKind::UnknownGolpe,
+                    Severity::Warning,
+                    location,
+                    Some("golpe"),
+                    format!("golpe {} is not in the golpe catalog", id),
+                );
+            }
+            id
+        }
+        (None, Some(sheet)) => {
+            let (severity, message) = if is_blank(cell) {
+                (
+                    Severity::Info,
+                    format!("golpe {} taken from the sheet name", sheet.id),
+                )
+            } else {
+                (
+                    Severity::Warning,
+                    format!(
+                        "could not read golpe from '{}', using {} from the sheet name",
+                        cell, sheet.id
+                    ),
+                )
+            };
+            report.push(
+                IssueKind::DefaultedValue,
+                severity,
+                location,
+                Some("golpe"),
+                message,
+            );
+            sheet.id
+        }
+        (None, None) => {
+            report.push(
+                IssueKind::DefaultedValue,
+                Severity::Warning,
+                location,
+                Some("golpe"),
+                format!("could not read golpe from '{}', using 0", cell),
+            );
+            0
+        }
+    }
+}
+
 /// Constructs an `Actividad` instance using data extracted from a row in an Excel sheet.
 ///
 /// # Arguments
 ///
 /// * `row` - A slice of `DataType` representing a row of data from the Excel sheet.
 /// * `start_index` - The starting index for extracting activity attributes.
-/// * `content` - A `HashMap<String, Content>` containing the activity's content details.
-/// * `phase` - A string indicating the phase of the activity (e.g., "WARM_UP").
+/// * `golpe` - The golpe of the row, as returned by `resolve_golpe`.
+/// * `phase` - The phase of the block (e.g., `Phase::WarmUp`).
+/// * `row_location` - The location of the whole row in the workbook.
+/// * `options` - The layout of the sheet and the translation memory.
+/// * `report` - The parse report where defaulted values, untranslated terms, machine
+///   translations and missing translations are recorded.
 ///
 /// # Returns
 ///
 /// An `Option<Actividad>` that is `Some(Actividad)` if the row has sufficient data to create a valid activity,
-/// or `None` if the data is incomplete.
+/// or `None` if the data is incomplete. The reason is recorded in `report`.
 pub fn create_actividad(
     row: &[DataType],
     start_index: usize,
-    content: HashMap<String, Content>,
-    phase: String,
+    golpe: i32,
+    phase: Phase,
+    row_location: &Provenance,
+    options: &ParseOptions,
+    report: &mut ParseReport,
 ) -> Option<Actividad> {
-    if row.len() > start_index + 9 {
-        Some(Actividad {
-            golpe: process_golpe(&row[0].to_string()).unwrap_or(0),
-            num_jugadores: process_num_jugadores(split_to_vec(&row[1].to_string().to_uppercase())),
-            typology: translate_typology_to_english(split_to_vec(
-                &row[2].to_string().to_uppercase(),
-            )),
-            level: translate_level_to_english(split_to_vec(&row[3].to_string().to_uppercase())),
-            id: row[start_index].to_string(),
-            model: translate_model_to_english(split_to_vec(
-                &row[start_index + 1].to_string().to_uppercase(),
-            )),
-            shot: translate_shot_to_english(split_to_vec(
-                &row[start_index + 2].to_string().to_uppercase(),
-            )),
-            part_to_practice: translate_part_to_practice(split_to_vec(
-                &row[start_index + 3].to_string().to_uppercase(),
-            )),
-            equipment: translate_material(split_to_vec(
-                &row[start_index + 4].to_string().to_uppercase(),
-            )),
-            duration: process_duration(&row[start_index + 5].to_string()),
-            content,
+    let layout = &options.layout;
+    let provenance = row_location.span(start_index, start_index + layout.block_width() - 1);
+    if row.len() < start_index + layout.block_width() {
+        report.push_skipped_block(
+            IssueKind::ShortRow,
+            Severity::Error,
+            provenance,
             phase,
-        })
-    } else {
-        None
+            format!(
+                "row has {} cells, the block needs {}",
+                row.len(),
+                start_index + layout.block_width()
+            ),
+        );
+        return None;
+    }
+    let mut content = create_content(row, start_index, layout);
+
+    // Skip blank blocks and reject blocks that only fill some of the required fields
+    let missing = missing_fields(row, start_index, &content, layout.primary_language());
+    if missing.len() == REQUIRED_BLOCK_FIELDS.len() + REQUIRED_CONTENT_FIELDS.len() {
+        report.push_skipped_block(
+            IssueKind::EmptyBlock,
+            Severity::Info,
+            provenance,
+            phase,
+            format!("{} block is empty, skipped", phase),
+        );
+        return None;
+    }
+    if !missing.is_empty() {
+        report.push_skipped_block(
+            IssueKind::PartialBlock,
+            Severity::Error,
+            provenance,
+            phase,
+            format!("{} block is missing {}, skipped", phase, missing.join(", ")),
+        );
+        return None;
     }
+
+    let (num_jugadores, unreadable) =
+        process_num_jugadores(split_cell(&row[1], "num_jugadores", layout));
+    for item in unreadable {
+        report.push(
+            IssueKind::DefaultedValue,
+            Severity::Warning,
+            row_location.cell(1),
+            Some("num_jugadores"),
+            format!("could not read a player count from '{}', ignored", item),
+        );
+    }
+
+    let typology =
+        translate_typology_to_english(split_cell(&row[2], "typology", layout), &options.vocabulary);
+    report_untranslated(report, &typology, row_location.cell(2), "typology");
+    let level =
+        translate_level_to_english(split_cell(&row[3], "level", layout), &options.vocabulary);
+    report_untranslated(report, &level, row_location.cell(3), "level");
+    let model = translate_model_to_english(
+        split_cell(&row[start_index + 1], "model", layout),
+        &options.vocabulary,
+    );
+    report_untranslated(report, &model, provenance.cell(1), "model");
+    let shot = translate_shot_to_english(
+        split_cell(&row[start_index + 2], "shot", layout),
+        &options.vocabulary,
+    );
+    report_untranslated(report, &shot, provenance.cell(2), "shot");
+    let part_to_practice = translate_part_to_practice(
+        split_cell(&row[start_index + 3], "part_to_practice", layout),
+        &options.vocabulary,
+    );
+    report_untranslated(
+        report,
+        &part_to_practice,
+        provenance.cell(3),
+        "part_to_practice",
+    );
+    let equipment = translate_material(
+        split_cell(&row[start_index + 4], "equipment", layout),
+        &options.vocabulary,
+    );
+    report_untranslated(report, &equipment, provenance.cell(4), "equipment");
+
+    // Fill the empty translations from the translation memory
+    if let Some(memory) = &options.translation_memory {
+        let targets: Vec<&str> = layout
+            .languages
+            .iter()
+            .skip(1)
+            .map(|language| language.code.as_str())
+            .collect();
+        for filled in memory.fill(&mut content, layout.primary_language(), &targets) {
+            let offset = layout
+                .content_offset(&filled.language, filled.field)
+                .unwrap_or_default();
+            report.push(
+                IssueKind::MachineTranslation,
+                Severity::Info,
+                provenance.cell(offset),
+                Some(&filled.language),
+                format!(
+                    "{} of activity {} filled from the translation memory ({})",
+                    filled.field, row[start_index], filled.match_kind
+                ),
+            );
+        }
+    }
+
+    // Report the languages of the layout the activity is not translated to
+    for language in layout.languages.iter().skip(1) {
+        let missing = missing_content_fields(&content, &language.code);
+        if !missing.is_empty() {
+            report.push(
+                IssueKind::MissingTranslation,
+                Severity::Warning,
+                provenance.cell(language.offset),
+                Some(&language.code),
+                if missing.len() == REQUIRED_CONTENT_FIELDS.len() {
+                    format!(
+                        "activity {} has no {} translation",
+                        row[start_index], language.code
+                    )
+                } else {
+                    format!(
+                        "activity {} is missing the {} {}",
+                        row[start_index],
+                        language.code,
+                        missing.join(", ")
+                    )
+                },
+            );
+        }
+    }
+
+    Some(Actividad {
+        golpe,
+        num_jugadores,
+        typology: typology.values,
+        level: level.values,
+        id: row[start_index].to_string(),
+        model: model.values,
+        shot: shot.values,
+        part_to_practice: part_to_practice.values,
+        equipment: equipment.values,
+        duration: process_duration(&row[start_index + 5].to_string()),
+        content,
+        phase,
+        provenance: Some(provenance),
+    })
 }
 
 /// Loads activities from an Excel sheet and organizes them into sections.
 ///
 /// Activities are categorized into four sections: warming up, main exercise (split into two parts),
-/// and the final part.
+/// and the final part. Skipped rows, short rows, empty blocks, defaulted values and untranslated
+/// terms are collected in a `ParseReport`.
 ///
 /// # Arguments
 ///
 /// * `path` - The file path to the Excel file.
 /// * `sheet_name` - The name of the sheet containing the activity data.
+/// * `options` - The layout of the sheet and the optional translation memory.
 ///
 /// # Returns
 ///
-/// A `Result<Seccion, Box<dyn Error>>` containing the activity sections, or an error message if the sheet cannot be read.
-pub fn load_actividades(path: &str, sheet_name: &str) -> Result<Seccion, Box<dyn Error>> {
+/// A `Result<(Seccion, ParseReport), Box<dyn Error>>` containing the activity sections and the parse report,
+/// or an error message if the sheet cannot be read.
+pub fn load_actividades(
+    path: &str,
+    sheet_name: &str,
+    options: &ParseOptions,
+) -> Result<(Seccion, ParseReport), Box<dyn Error>> {
+    let layout = &options.layout;
     let mut workbook: Xlsx<_> = open_workbook(path)?;
     if let Some(Ok(range)) = workbook.worksheet_range(sheet_name) {
-        let mut calentamiento = Vec::new();
-        let mut ejercicio1 = Vec::new();
-        let mut ejercicio2 = Vec::new();
-        let mut parte_final = Vec::new();
-
-        for row in range.rows().skip(3) {
-            if row.len() >= 68 {
-                // Extract warming-up activities
-                if let Some(act) =
-                    create_actividad(row, 4, create_content(row, 11), "WARM_UP".to_string())
-                {
-                    calentamiento.push(act);
-                }
-                // Extract main exercise phase 1
-                if let Some(act) = create_actividad(
-                    row,
-                    20,
-                    create_content(row, 27),
-                    "MAIN_EXERCISE".to_string(),
-                ) {
-                    ejercicio1.push(act);
-                }
-                // Extract main exercise phase 2
+        let mut report = ParseReport::default();
+
+        // Offset of the range in the sheet, used to report 1-based cell references
+        let (first_row, first_column) = range
+            .start()
+            .map(|(r, c)| (r as usize, c as usize))
+            .unwrap_or((0, 0));
+
+        let sheet_golpe = golpe_of_sheet(
+            sheet_name,
+            Provenance::new(path, sheet_name, first_row + 1, first_column, first_column),
+            &options.golpes,
+            &mut report,
+        );
+        let mut seccion = Seccion::new(sheet_golpe.cloned(), layout.phases());
+
+        for (index, row) in range.rows().enumerate().skip(3) {
+            let row_location = Provenance::new(
+                path,
+                sheet_name,
+                first_row + index + 1,
+                first_column,
+                first_column + row.len().max(1) - 1,
+            );
+
+            if row.iter().all(is_blank) {
+                report.push(
+                    IssueKind::SkippedRow,
+                    Severity::Info,
+                    row_location,
+                    None,
+                    "blank row",
+                );
+                continue;
+            }
+            if row.len() < layout.min_row_len() {
+                report.push(
+                    IssueKind::ShortRow,
+                    Severity::Error,
+                    row_location,
+                    None,
+                    format!(
+                        "row has {} cells, {} expected; it was skipped",
+                        row.len(),
+                        layout.min_row_len()
+                    ),
+                );
+                continue;
+            }
+
+            let golpe = resolve_golpe(
+                &row[0],
+                row_location.cell(0),
+                sheet_golpe,
+                &options.golpes,
+                &mut report,
+            );
+
+            // Extract the activities of each phase block
+            for block in &layout.blocks {
                 if let Some(act) = create_actividad(
                     row,
-                    36,
-                    create_content(row, 43),
-                    "MAIN_EXERCISE".to_string(),
+                    block.start,
+                    golpe,
+                    block.phase,
+                    &row_location,
+                    options,
+                    &mut report,
                 ) {
-                    ejercicio2.push(act);
-                }
-                // Extract final part activities
-                if let Some(act) =
-                    create_actividad(row, 52, create_content(row, 59), "FINAL_PART".to_string())
-                {
-                    parte_final.push(act);
+                    seccion.fase_mut(block.phase).push(act);
                 }
             }
         }
 
-        Ok(Seccion {
-            calentamiento,
-            ejercicio1,
-            ejercicio2,
-            parte_final,
-        })
+        if seccion.golpe.is_none() {
+            let golpe = seccion
+                .actividades()
+                .find_map(|actividad| options.golpes.find(actividad.golpe))
+                .cloned();
+            seccion.golpe = golpe;
+        }
+        Ok((seccion, report))
     } else {
         Err("Worksheet not found or unreadable.".into())
     }
@@ -0,0 +1,185 @@
+use super::layout::SheetLayout;
+use crate::models::{activity::Actividad, activity_sections::Seccion};
+use rust_xlsxwriter::Workbook;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
+
+/// Output formats supported when exporting activities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A single JSON array with every activity.
+    Json,
+    /// One JSON activity per line (JSON Lines).
+    Jsonl,
+    /// An Excel workbook with the same layout read by `load_actividades`.
+    Excel,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "jsonl" => Ok(ExportFormat::Jsonl),
+            "excel" | "xlsx" => Ok(ExportFormat::Excel),
+            other => Err(format!("Unknown export format: {}", other)),
+        }
+    }
+}
+
+/// Header labels for the columns of a phase block, relative to the block start.
+const BLOCK_HEADERS: [(usize, &str); 6] = [
+    (0, "ID"),
+    (1, "MODELO"),
+    (2, "GOLPE"),
+    (3, "PARTE A TRABAJAR"),
+    (4, "MATERIAL"),
+    (5, "DURACIÓN"),
+];
+
+/// Exports the sections in the requested format.
+///
+/// # Arguments
+///
+/// * `secciones` - The sections to export, keyed by golpe.
+/// * `format` - The output format.
+/// * `path` - The destination file.
+/// * `layout` - The sheet layout used for Excel exports.
+///
+/// # Returns
+///
+/// A `Result<(), Box<dyn Error>>` indicating whether the file was written.
+pub fn export_secciones(
+    secciones: &BTreeMap<i32, Seccion>,
+    format: ExportFormat,
+    path: &str,
+    layout: &SheetLayout,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        ExportFormat::Json => export_json(secciones, path),
+        ExportFormat::Jsonl => export_jsonl(secciones, path),
+        ExportFormat::Excel => export_excel(secciones, path, layout),
+    }
+}
+
+/// Writes every activity as a single pretty-printed JSON array.
+pub fn export_json(secciones: &BTreeMap<i32, Seccion>, path: &str) -> Result<(), Box<dyn Error>> {
+    let actividades: Vec<&Actividad> = secciones.values().flat_map(Seccion::actividades).collect();
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &actividades)?;
+    Ok(())
+}
+
+/// Writes one JSON activity per line.
+pub fn export_jsonl(secciones: &BTreeMap<i32, Seccion>, path: &str) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for actividad in secciones.values().flat_map(Seccion::actividades) {
+        serde_json::to_writer(&mut writer, actividad)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes a workbook with one sheet per golpe, using the layout read by `load_actividades`.
+///
+/// Each activity gets its own row, filled only in the block of its phase, so the
+/// workbook can be loaded again or used to seed a new programming table. Content is
+/// written for every language of the layout.
+pub fn export_excel(
+    secciones: &BTreeMap<i32, Seccion>,
+    path: &str,
+    layout: &SheetLayout,
+) -> Result<(), Box<dyn Error>> {
+    let mut workbook = Workbook::new();
+
+    for (golpe, seccion) in secciones {
+        // Name the sheets like the programming tables, so the golpe can be read back
+        let sheet_name = match &seccion.golpe {
+            Some(known) => format!("{}. {}", known.id, known.name_es),
+            None => golpe.to_string(),
+        };
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&sheet_name)?;
+
+        // Header rows, skipped when the sheet is loaded again
+        worksheet.write_string(0, 0, &sheet_name)?;
+        worksheet.write_string(2, 0, "GOLPE")?;
+        worksheet.write_string(2, 1, "Nº JUGADORES")?;
+        worksheet.write_string(2, 2, "TIPOLOGÍA")?;
+        worksheet.write_string(2, 3, "NIVEL")?;
+        for block in &layout.blocks {
+            let start = block.start;
+            worksheet.write_string(1, start as u16, block.phase.sheet_label())?;
+            for (offset, header) in BLOCK_HEADERS {
+                worksheet.write_string(2, (start + offset) as u16, header)?;
+            }
+            for language in &layout.languages {
+                for (offset, header) in ["TÍTULO", "OBJETIVO", "GUION"].iter().enumerate() {
+                    worksheet.write_string(
+                        2,
+                        (start + language.offset + offset) as u16,
+                        format!("{} {}", header, language.code),
+                    )?;
+                }
+            }
+        }
+        // Label the last column so the sheet keeps the width `load_actividades` expects
+        worksheet.write_string(2, (layout.min_row_len() - 1) as u16, "FIN")?;
+
+        let mut row = 3;
+        for fase in &seccion.fases {
+            let Some(block) = layout.blocks.iter().find(|block| block.phase == fase.phase) else {
+                if !fase.actividades.is_empty() {
+                    eprintln!(
+                        "Skipping {} activities of phase {}, which has no block in the layout",
+                        fase.actividades.len(),
+                        fase.phase
+                    );
+                }
+                continue;
+            };
+            for actividad in &fase.actividades {
+                write_actividad_row(worksheet, row, block.start as u16, actividad, layout)?;
+                row += 1;
+            }
+        }
+    }
+
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// Writes the shared columns and the phase block of a single activity.
+fn write_actividad_row(
+    worksheet: &mut rust_xlsxwriter::Worksheet,
+    row: u32,
+    start: u16,
+    actividad: &Actividad,
+    layout: &SheetLayout,
+) -> Result<(), Box<dyn Error>> {
+    worksheet.write_string(row, 0, actividad.golpe.to_string())?;
+    worksheet.write_string(row, 1, actividad.num_jugadores.to_string())?;
+    worksheet.write_string(row, 2, actividad.typology.join(", "))?;
+    worksheet.write_string(row, 3, actividad.level.join(", "))?;
+
+    worksheet.write_string(row, start, &actividad.id)?;
+    worksheet.write_string(row, start + 1, actividad.model.join(", "))?;
+    worksheet.write_string(row, start + 2, actividad.shot.join(", "))?;
+    worksheet.write_string(row, start + 3, actividad.part_to_practice.join(", "))?;
+    worksheet.write_string(row, start + 4, actividad.equipment.join(", "))?;
+    worksheet.write_string(row, start + 5, &actividad.duration)?;
+    for language in &layout.languages {
+        if let Some(content) = actividad.content.get(&language.code) {
+            let column = start + language.offset as u16;
+            worksheet.write_string(row, column, &content.title)?;
+            worksheet.write_string(row, column + 1, &content.goal)?;
+            worksheet.write_string(row, column + 2, &content.script)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,28 @@
+// utils/get_request.rs
+use super::post_request::{ApiClient, HttpStatusError};
+use reqwest::StatusCode;
+use std::error::Error;
+
+/// Sends a GET request to a listing endpoint.
+///
+/// # Arguments
+/// * `client` - The HTTP client.
+/// * `endpoint` - The URL of the API endpoint.
+///
+/// # Returns
+/// * `Result<String, Box<dyn Error>>` - The response body from the server or an error.
+pub fn get_request(client: &ApiClient, endpoint: &str) -> Result<String, Box<dyn Error>> {
+    match client.get(endpoint) {
+        Ok(response) => Ok(response.body),
+        Err(e) => match e.downcast_ref::<HttpStatusError>() {
+            Some(error) => Err(format!(
+                "Error al descargar las actividades: {}",
+                StatusCode::from_u16(error.status)
+                    .map(|status| status.to_string())
+                    .unwrap_or_else(|_| error.status.to_string())
+            )
+            .into()),
+            None => Err(e),
+        },
+    }
+}
@@ -0,0 +1,129 @@
+use super::vocabulary::{canonical_term, VocabularyOptions};
+use crate::models::golpe::Golpe;
+use regex::Regex;
+use serde::Deserialize;
+use std::error::Error;
+use std::sync::LazyLock;
+
+/// Golpe id and stroke name of a sheet name (e.g., "1. DERECHA PLANA", "2) REVÉS").
+static SHEET_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(\d+)\s*[.)\-]?\s*(.*?)\s*$").expect("valid regex"));
+
+/// Strokes known by the program, as (id, Spanish name, English name, API code).
+const DEFAULT_GOLPES: [(i32, &str, &str, &str); 10] = [
+    (1, "DERECHA PLANA", "Flat Forehand", "FLAT_FOREHAND"),
+    (2, "REVÉS PLANO", "Flat Backhand", "FLAT_BACKHAND"),
+    (3, "VOLEA DE DERECHA", "Forehand Volley", "FOREHAND_VOLLEY"),
+    (4, "VOLEA DE REVÉS", "Backhand Volley", "BACKHAND_VOLLEY"),
+    (5, "BANDEJA", "Tray", "TRAY"),
+    (6, "VÍBORA", "Vibora", "VIBORA"),
+    (7, "REMATE", "Smash", "SMASH"),
+    (8, "GLOBO", "Lob", "LOB"),
+    (9, "SAQUE", "Serve", "SERVE"),
+    (10, "RESTO", "Return", "RETURN"),
+];
+
+/// Catalog of the strokes, used to link the sheet names and the golpe ids of column A.
+///
+/// The built-in catalog can be replaced with a JSON file:
+///
+/// ```json
+/// { "golpes": [ { "id": 1, "name_es": "DERECHA PLANA", "name_en": "Flat Forehand", "api_code": "FLAT_FOREHAND" } ] }
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct GolpeCatalog {
+    /// Known strokes.
+    pub golpes: Vec<Golpe>,
+}
+
+impl Default for GolpeCatalog {
+    fn default() -> Self {
+        GolpeCatalog {
+            golpes: DEFAULT_GOLPES
+                .iter()
+                .map(|(id, name_es, name_en, api_code)| Golpe {
+                    id: *id,
+                    name_es: name_es.to_string(),
+                    name_en: name_en.to_string(),
+                    api_code: api_code.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl GolpeCatalog {
+    /// Finds a stroke by id.
+    pub fn find(&self, id: i32) -> Option<&Golpe> {
+        self.golpes.iter().find(|golpe| golpe.id == id)
+    }
+
+    /// Finds a stroke by Spanish name, ignoring case, accents and spacing.
+    pub fn find_by_name(&self, name: &str) -> Option<&Golpe> {
+        let options = VocabularyOptions::default();
+        let name = canonical_term(name, &options);
+        self.golpes
+            .iter()
+            .find(|golpe| canonical_term(&golpe.name_es, &options) == name)
+    }
+}
+
+/// Loads a golpe catalog from a JSON file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON file.
+///
+/// # Returns
+///
+/// A `Result<GolpeCatalog, Box<dyn Error>>` with the catalog, or an error if an id is repeated.
+pub fn load_golpe_catalog(path: &str) -> Result<GolpeCatalog, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let catalog: GolpeCatalog = serde_json::from_str(&data)?;
+    for (index, golpe) in catalog.golpes.iter().enumerate() {
+        if catalog.golpes[..index]
+            .iter()
+            .any(|other| other.id == golpe.id)
+        {
+            return Err(format!("Golpe declared twice in the catalog: {}", golpe.id).into());
+        }
+    }
+    Ok(catalog)
+}
+
+/// Splits a sheet name like "1. DERECHA PLANA" into the golpe id and the stroke name.
+///
+/// # Arguments
+///
+/// * `sheet_name` - The name of the sheet.
+///
+/// # Returns
+///
+/// An `Option<(i32, String)>` with the id and the name, or `None` if the name does not start with a number.
+pub fn golpe_from_sheet_name(sheet_name: &str) -> Option<(i32, String)> {
+    let captures = SHEET_NAME.captures(sheet_name)?;
+    let id = captures[1].parse().ok()?;
+    Some((id, captures[2].to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_sheet_name() {
+        assert_eq!(
+            golpe_from_sheet_name("1. DERECHA PLANA"),
+            Some((1, "DERECHA PLANA".to_string()))
+        );
+        assert_eq!(
+            golpe_from_sheet_name(" 12) REVÉS "),
+            Some((12, "REVÉS".to_string()))
+        );
+        assert_eq!(
+            golpe_from_sheet_name("3-VOLEA"),
+            Some((3, "VOLEA".to_string()))
+        );
+        assert_eq!(golpe_from_sheet_name("NOTAS"), None);
+    }
+}
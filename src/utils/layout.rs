@@ -0,0 +1,295 @@
+use crate::models::phase::Phase;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// ISO 639-1 language codes accepted for content columns.
+const ISO_639_1: [&str; 183] = [
+    "AA", "AB", "AE", "AF", "AK", "AM", "AN", "AR", "AS", "AV", "AY", "AZ", "BA", "BE", "BG", "BI",
+    "BM", "BN", "BO", "BR", "BS", "CA", "CE", "CH", "CO", "CR", "CS", "CU", "CV", "CY", "DA", "DE",
+    "DV", "DZ", "EE", "EL", "EN", "EO", "ES", "ET", "EU", "FA", "FF", "FI", "FJ", "FO", "FR", "FY",
+    "GA", "GD", "GL", "GN", "GU", "GV", "HA", "HE", "HI", "HO", "HR", "HT", "HU", "HY", "HZ", "IA",
+    "ID", "IE", "IG", "II", "IK", "IO", "IS", "IT", "IU", "JA", "JV", "KA", "KG", "KI", "KJ", "KK",
+    "KL", "KM", "KN", "KO", "KR", "KS", "KU", "KV", "KW", "KY", "LA", "LB", "LG", "LI", "LN", "LO",
+    "LT", "LU", "LV", "MG", "MH", "MI", "MK", "ML", "MN", "MR", "MS", "MT", "MY", "NA", "NB", "ND",
+    "NE", "NG", "NL", "NN", "NO", "NR", "NV", "NY", "OC", "OJ", "OM", "OR", "OS", "PA", "PI", "PL",
+    "PS", "PT", "QU", "RM", "RN", "RO", "RU", "RW", "SA", "SC", "SD", "SE", "SG", "SI", "SK", "SL",
+    "SM", "SN", "SO", "SQ", "SR", "SS", "ST", "SU", "SV", "SW", "TA", "TE", "TG", "TH", "TI", "TK",
+    "TL", "TN", "TO", "TR", "TS", "TT", "TW", "TY", "UG", "UK", "UR", "UZ", "VE", "VI", "VO", "WA",
+    "WO", "XH", "YI", "YO", "ZA", "ZH", "ZU",
+];
+
+/// Minimum number of columns spanned by each phase block, starting at the activity ID.
+const MIN_BLOCK_WIDTH: usize = 15;
+
+/// Number of columns shared by every block of a row (golpe, players, typology and level).
+const SHARED_COLUMNS: usize = 4;
+
+/// Activity fields read from multi-value cells.
+const LIST_FIELDS: [&str; 7] = [
+    "num_jugadores",
+    "typology",
+    "level",
+    "model",
+    "shot",
+    "part_to_practice",
+    "equipment",
+];
+
+/// Columns holding the title, goal and script of one language in each phase block.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LanguageColumns {
+    /// ISO 639-1 code of the language (e.g., "ES"), used as the key of `Actividad.content`.
+    pub code: String,
+
+    /// Offset of the title column from the start of the block. The goal and the script
+    /// are read from the two following columns.
+    pub offset: usize,
+}
+
+/// A phase block of the sheet.
+#[derive(Debug, Deserialize, Clone)]
+pub struct BlockLayout {
+    /// 0-based column where the block starts (the activity ID column).
+    pub start: usize,
+
+    /// Phase of the activities of the block (e.g., "MAIN_EXERCISE_2").
+    pub phase: Phase,
+}
+
+/// Describes where the data of each activity is found in a sheet.
+///
+/// The default layout matches the programming tables used so far: four phase blocks
+/// (warm-up, two main exercises and final part) starting at columns E, U, AK and BA,
+/// with the Spanish content seven columns after the activity ID.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SheetLayout {
+    /// Phase blocks of the sheet, in column order.
+    #[serde(default = "default_blocks")]
+    pub blocks: Vec<BlockLayout>,
+
+    /// Shorthand for `blocks`: the 0-based start column of each block. The first block
+    /// is the warm-up, the last one the final part and the ones in between the main
+    /// exercises. It replaces `blocks` when given.
+    #[serde(default)]
+    block_starts: Option<Vec<usize>>,
+
+    /// Content column groups, one per language. The first one is the primary language,
+    /// which every activity must fill.
+    #[serde(default = "default_languages")]
+    pub languages: Vec<LanguageColumns>,
+
+    /// Separators of the values of multi-value cells, used by every list field
+    /// without its own entry in `field_separators`; only "," when missing.
+    #[serde(default = "default_separators")]
+    pub separators: Vec<String>,
+
+    /// Separators of specific list fields (e.g., `"shot": [","]`).
+    #[serde(default)]
+    pub field_separators: HashMap<String, Vec<String>>,
+
+    /// Whether repeated values of a multi-value cell are kept only once.
+    #[serde(default)]
+    pub dedupe_lists: bool,
+}
+
+fn default_blocks() -> Vec<BlockLayout> {
+    blocks_from_starts(&[4, 20, 36, 52])
+}
+
+/// Assigns the phases to blocks by position: warm-up, main exercises and final part.
+fn blocks_from_starts(starts: &[usize]) -> Vec<BlockLayout> {
+    starts
+        .iter()
+        .enumerate()
+        .map(|(position, start)| BlockLayout {
+            start: *start,
+            phase: match position {
+                0 => Phase::WarmUp,
+                _ if position + 1 == starts.len() => Phase::FinalPart,
+                _ => Phase::MainExercise {
+                    index: position as u32,
+                },
+            },
+        })
+        .collect()
+}
+
+fn default_languages() -> Vec<LanguageColumns> {
+    vec![LanguageColumns {
+        code: "ES".to_string(),
+        offset: 7,
+    }]
+}
+
+fn default_separators() -> Vec<String> {
+    vec![",".to_string()]
+}
+
+impl Default for SheetLayout {
+    fn default() -> Self {
+        SheetLayout {
+            blocks: default_blocks(),
+            block_starts: None,
+            languages: default_languages(),
+            separators: default_separators(),
+            field_separators: HashMap::new(),
+            dedupe_lists: false,
+        }
+    }
+}
+
+impl SheetLayout {
+    /// Code of the primary language of the content.
+    pub fn primary_language(&self) -> &str {
+        self.languages
+            .first()
+            .map(|language| language.code.as_str())
+            .unwrap_or("ES")
+    }
+
+    /// Number of columns spanned by each phase block, including every language group.
+    pub fn block_width(&self) -> usize {
+        self.languages
+            .iter()
+            .map(|language| language.offset + 3)
+            .fold(MIN_BLOCK_WIDTH, usize::max)
+    }
+
+    /// Minimum number of cells a data row must have to hold every block, plus the
+    /// closing column that follows the last block.
+    pub fn min_row_len(&self) -> usize {
+        let last_start = self
+            .blocks
+            .iter()
+            .map(|block| block.start)
+            .max()
+            .unwrap_or(SHARED_COLUMNS);
+        last_start + self.block_width() + 1
+    }
+
+    /// Phases of the blocks, in column order.
+    pub fn phases(&self) -> impl Iterator<Item = Phase> + '_ {
+        self.blocks.iter().map(|block| block.phase)
+    }
+
+    /// Offset of a content field ("title", "goal" or "script") of a language from the block start.
+    pub fn content_offset(&self, code: &str, field: &str) -> Option<usize> {
+        let language = self
+            .languages
+            .iter()
+            .find(|language| language.code.eq_ignore_ascii_case(code))?;
+        match field {
+            "title" => Some(language.offset),
+            "goal" => Some(language.offset + 1),
+            "script" => Some(language.offset + 2),
+            _ => None,
+        }
+    }
+
+    /// Separators of the multi-value cells of a list field.
+    pub fn separators(&self, field: &str) -> &[String] {
+        self.field_separators.get(field).unwrap_or(&self.separators)
+    }
+
+    /// Checks that the blocks do not overlap, that every language code is a valid,
+    /// unique ISO 639-1 code and that the separators are usable.
+    ///
+    /// Codes are normalized to uppercase, the form used as key of `Actividad.content`.
+    /// Separators are uppercased too, as cells are uppercased before being split.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<(), Box<dyn Error>>` describing the first invalid language or separator, if any.
+    pub fn validate(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(starts) = self.block_starts.take() {
+            if starts.len() < 2 {
+                return Err("block_starts needs at least a warm-up and a final block.".into());
+            }
+            self.blocks = blocks_from_starts(&starts);
+        }
+        if self.blocks.is_empty() {
+            return Err("The layout must declare at least one block.".into());
+        }
+        if self.languages.is_empty() {
+            return Err("The layout must declare at least one language.".into());
+        }
+        for index in 0..self.languages.len() {
+            let code = self.languages[index].code.trim().to_uppercase();
+            if !ISO_639_1.contains(&code.as_str()) {
+                return Err(format!("Invalid ISO 639-1 language code: {}", code).into());
+            }
+            if self.languages[..index]
+                .iter()
+                .any(|other| other.code == code)
+            {
+                return Err(format!("Language declared twice in the layout: {}", code).into());
+            }
+            self.languages[index].code = code;
+        }
+        self.blocks.sort_by_key(|block| block.start);
+        for index in 0..self.blocks.len() {
+            let block = &self.blocks[index];
+            if block.start < SHARED_COLUMNS {
+                return Err(format!(
+                    "The {} block starts at column {}, inside the shared columns.",
+                    block.phase, block.start
+                )
+                .into());
+            }
+            if let Some(previous) = index.checked_sub(1).map(|previous| &self.blocks[previous]) {
+                if block.start < previous.start + self.block_width() {
+                    return Err(format!(
+                        "The {} block overlaps the {} block.",
+                        block.phase, previous.phase
+                    )
+                    .into());
+                }
+            }
+            if self.blocks[..index]
+                .iter()
+                .any(|other| other.phase == block.phase)
+            {
+                return Err(format!("Phase declared twice in the layout: {}", block.phase).into());
+            }
+        }
+        if let Some(field) = self
+            .field_separators
+            .keys()
+            .find(|field| !LIST_FIELDS.contains(&field.as_str()))
+        {
+            return Err(format!(
+                "Separators declared for a field that is not a list: {}",
+                field
+            )
+            .into());
+        }
+        for separators in
+            std::iter::once(&mut self.separators).chain(self.field_separators.values_mut())
+        {
+            for separator in separators {
+                if separator.is_empty() {
+                    return Err("Separators cannot be empty.".into());
+                }
+                *separator = separator.to_uppercase();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads a sheet layout from a JSON file and validates its language codes.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON file.
+///
+/// # Returns
+///
+/// A `Result<SheetLayout, Box<dyn Error>>` with the layout.
+pub fn load_layout(path: &str) -> Result<SheetLayout, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let mut layout: SheetLayout = serde_json::from_str(&data)?;
+    layout.validate()?;
+    Ok(layout)
+}
@@ -0,0 +1,361 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+
+/// Case conversion applied to the string values of a field.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseConversion {
+    /// "BACKHAND SIDE WALL"
+    Upper,
+    /// "backhand side wall"
+    Lower,
+    /// "Backhand Side Wall"
+    Title,
+    /// "backhand_side_wall"
+    Snake,
+    /// "BACKHAND_SIDE_WALL"
+    ScreamingSnake,
+    /// "backhand-side-wall"
+    Kebab,
+    /// "backhandSideWall"
+    Camel,
+}
+
+impl CaseConversion {
+    /// Converts a text. Words are split on any character that is not a letter or a digit.
+    pub fn apply(self, text: &str) -> String {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        };
+        match self {
+            CaseConversion::Upper => text.to_uppercase(),
+            CaseConversion::Lower => text.to_lowercase(),
+            CaseConversion::Title => words.iter().map(capitalize).collect::<Vec<_>>().join(" "),
+            CaseConversion::Snake => words.join("_"),
+            CaseConversion::ScreamingSnake => words.join("_").to_uppercase(),
+            CaseConversion::Kebab => words.join("-"),
+            CaseConversion::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(index, word)| match index {
+                    0 => word.clone(),
+                    _ => capitalize(word),
+                })
+                .collect(),
+        }
+    }
+
+    /// Converts every string of a value, including the ones inside arrays and objects.
+    fn apply_to_value(self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.apply(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.apply_to_value(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.apply_to_value(item)),
+            _ => {}
+        }
+    }
+}
+
+/// How a single field of the activity is written in the payload.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum FieldMapping {
+    /// The field is moved to another path, e.g. `"players"` or `"details.players"`.
+    Rename(String),
+    /// The field is moved and/or its values converted.
+    Convert {
+        /// Target path; the field keeps its path when missing.
+        #[serde(default)]
+        to: Option<String>,
+        /// Case conversion of the string values.
+        #[serde(default)]
+        case: Option<CaseConversion>,
+    },
+}
+
+/// Transformation applied to each serialized activity before it is sent.
+///
+/// Paths are dotted (e.g., "content.ES.title") and address nested objects. A mapping
+/// file looks like:
+///
+/// ```json
+/// {
+///   "fields": {
+///     "num_jugadores": "players",
+///     "golpe": "strokeId",
+///     "content": "details.content",
+///     "shot": { "to": "details.shots", "case": "snake" }
+///   },
+///   "drop": ["typology"],
+///   "constants": { "source": "excel", "meta.schemaVersion": 2 },
+///   "keep_unmapped": true
+/// }
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct PayloadMapping {
+    /// Fields to move or convert, keyed by source path.
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldMapping>,
+
+    /// Source paths left out of the payload.
+    #[serde(default)]
+    pub drop: Vec<String>,
+
+    /// Values added to the payload, keyed by target path.
+    #[serde(default)]
+    pub constants: BTreeMap<String, Value>,
+
+    /// Whether fields not listed in `fields` are copied unchanged.
+    #[serde(default = "default_keep_unmapped")]
+    pub keep_unmapped: bool,
+}
+
+fn default_keep_unmapped() -> bool {
+    true
+}
+
+impl PayloadMapping {
+    /// Applies the mapping to a serialized activity.
+    ///
+    /// Listed fields are moved and converted first, then the dropped fields are removed
+    /// and, if `keep_unmapped` is set, the remaining fields are copied at their path.
+    /// Constants are added last and replace any value already at their path.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The serialized activity.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<Value, Box<dyn Error>>` with the mapped payload, or an error if a path
+    /// crosses a value that is not an object.
+    pub fn apply(&self, mut value: Value) -> Result<Value, Box<dyn Error>> {
+        let mut mapped = Vec::new();
+        for (source, mapping) in &self.fields {
+            let Some(mut field) = remove_path(&mut value, source) else {
+                continue;
+            };
+            let target = match mapping {
+                FieldMapping::Rename(to) => to,
+                FieldMapping::Convert { to, case } => {
+                    if let Some(case) = case {
+                        case.apply_to_value(&mut field);
+                    }
+                    to.as_ref().unwrap_or(source)
+                }
+            };
+            mapped.push((target, field));
+        }
+        for source in &self.drop {
+            remove_path(&mut value, source);
+        }
+
+        let mut payload = if self.keep_unmapped {
+            value
+        } else {
+            Value::Object(Map::new())
+        };
+        for (target, field) in mapped {
+            insert_path(&mut payload, target, field)?;
+        }
+        for (target, constant) in &self.constants {
+            insert_path(&mut payload, target, constant.clone())?;
+        }
+        Ok(payload)
+    }
+}
+
+/// Loads a payload mapping from a JSON file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON file.
+///
+/// # Returns
+///
+/// A `Result<PayloadMapping, Box<dyn Error>>` with the mapping, or an error if two fields
+/// are written to the same path.
+pub fn load_payload_mapping(path: &str) -> Result<PayloadMapping, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let mapping: PayloadMapping = serde_json::from_str(&data)?;
+
+    let mut targets = HashSet::new();
+    for (source, field) in &mapping.fields {
+        let target = match field {
+            FieldMapping::Rename(to) => to,
+            FieldMapping::Convert { to, .. } => to.as_ref().unwrap_or(source),
+        };
+        if target.split('.').any(str::is_empty) {
+            return Err(format!("Invalid target path in the payload mapping: '{}'", target).into());
+        }
+        if !targets.insert(target.as_str()) {
+            return Err(format!("Two fields are mapped to '{}'", target).into());
+        }
+    }
+    Ok(mapping)
+}
+
+/// Removes the value at a dotted path, returning it.
+fn remove_path(value: &mut Value, path: &str) -> Option<Value> {
+    match path.split_once('.') {
+        Some((head, rest)) => remove_path(value.get_mut(head)?, rest),
+        None => value.as_object_mut()?.remove(path),
+    }
+}
+
+/// Inserts a value at a dotted path, creating the intermediate objects.
+fn insert_path(value: &mut Value, path: &str, field: Value) -> Result<(), Box<dyn Error>> {
+    let object = value.as_object_mut().ok_or_else(|| {
+        format!(
+            "Cannot write '{}' inside a value that is not an object",
+            path
+        )
+    })?;
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            let child = object
+                .entry(head.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            insert_path(child, rest, field)
+        }
+        None => {
+            object.insert(path.to_string(), field);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mapping(value: Value) -> PayloadMapping {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn activity() -> Value {
+        json!({
+            "id": "D-C1",
+            "golpe": 1,
+            "num_jugadores": [2, 3, 4],
+            "typology": ["IMPROVEMENT"],
+            "shot": ["FOREHAND", "BACKHAND SIDE WALL"],
+            "content": { "ES": { "title": "Calentar", "goal": "Activar" } }
+        })
+    }
+
+    #[test]
+    fn renames_fields() {
+        let payload =
+            mapping(json!({ "fields": { "num_jugadores": "players", "golpe": "strokeId" } }))
+                .apply(activity())
+                .unwrap();
+        assert_eq!(payload["players"], json!([2, 3, 4]));
+        assert_eq!(payload["strokeId"], json!(1));
+        assert!(payload.get("num_jugadores").is_none());
+        assert!(payload.get("golpe").is_none());
+    }
+
+    #[test]
+    fn nests_and_unnests_fields() {
+        let payload = mapping(json!({
+            "fields": { "golpe": "stroke.id", "content.ES.title": "title" }
+        }))
+        .apply(activity())
+        .unwrap();
+        assert_eq!(payload["stroke"], json!({ "id": 1 }));
+        assert_eq!(payload["title"], json!("Calentar"));
+        assert_eq!(payload["content"], json!({ "ES": { "goal": "Activar" } }));
+    }
+
+    #[test]
+    fn converts_case() {
+        let payload = mapping(json!({
+            "fields": {
+                "shot": { "to": "details.shots", "case": "snake" },
+                "content": { "case": "upper" }
+            }
+        }))
+        .apply(activity())
+        .unwrap();
+        assert_eq!(
+            payload["details"]["shots"],
+            json!(["forehand", "backhand_side_wall"])
+        );
+        assert_eq!(payload["content"]["ES"]["title"], json!("CALENTAR"));
+
+        let text = "BACKHAND SIDE-WALL";
+        assert_eq!(CaseConversion::Lower.apply(text), "backhand side-wall");
+        assert_eq!(CaseConversion::Title.apply(text), "Backhand Side Wall");
+        assert_eq!(
+            CaseConversion::ScreamingSnake.apply(text),
+            "BACKHAND_SIDE_WALL"
+        );
+        assert_eq!(CaseConversion::Kebab.apply(text), "backhand-side-wall");
+        assert_eq!(CaseConversion::Camel.apply(text), "backhandSideWall");
+    }
+
+    #[test]
+    fn drops_fields() {
+        let payload = mapping(json!({ "drop": ["typology", "content.ES.goal", "missing"] }))
+            .apply(activity())
+            .unwrap();
+        assert!(payload.get("typology").is_none());
+        assert_eq!(payload["content"], json!({ "ES": { "title": "Calentar" } }));
+        assert_eq!(payload["id"], json!("D-C1"));
+    }
+
+    #[test]
+    fn adds_constants_last() {
+        let payload = mapping(json!({
+            "fields": { "golpe": "source" },
+            "constants": { "source": "excel", "meta.schemaVersion": 2 }
+        }))
+        .apply(activity())
+        .unwrap();
+        assert_eq!(payload["source"], json!("excel"));
+        assert_eq!(payload["meta"], json!({ "schemaVersion": 2 }));
+    }
+
+    #[test]
+    fn keeps_only_mapped_fields_when_asked() {
+        let payload = mapping(json!({
+            "fields": { "id": "activityId", "shot": { "case": "kebab" } },
+            "constants": { "source": "excel" },
+            "keep_unmapped": false
+        }))
+        .apply(activity())
+        .unwrap();
+        assert_eq!(
+            payload,
+            json!({
+                "activityId": "D-C1",
+                "shot": ["forehand", "backhand-side-wall"],
+                "source": "excel"
+            })
+        );
+    }
+
+    #[test]
+    fn writing_inside_a_non_object_fails() {
+        let error = mapping(json!({ "fields": { "golpe": "id.value" } }))
+            .apply(activity())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Cannot write 'value' inside a value that is not an object"
+        );
+    }
+}
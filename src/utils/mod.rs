@@ -1,3 +1,23 @@
+pub mod batch;
+pub mod cli;
 pub mod excel;
+pub mod export;
+pub mod get_request;
+pub mod golpes;
+pub mod layout;
+pub mod mapping;
+pub mod openapi;
 pub mod parser;
+pub mod payload;
 pub mod post_request;
+pub mod profiles;
+pub mod pull;
+pub mod routing;
+pub mod schema;
+pub mod session;
+pub mod signing;
+pub mod state;
+pub mod throttle;
+pub mod translation_memory;
+pub mod validation;
+pub mod vocabulary;
@@ -0,0 +1,410 @@
+use super::schema::validate_json_in;
+use reqwest::Url;
+use serde_json::Value;
+use std::error::Error;
+
+/// Media type of the request and response bodies.
+const JSON_MEDIA_TYPE: &str = "application/json";
+
+/// OpenAPI specification of the API, loaded from a JSON file.
+#[derive(Debug, Clone)]
+pub struct OpenApiSpec {
+    /// Path of the file, for error messages.
+    source: String,
+
+    /// The whole specification.
+    document: Value,
+}
+
+/// Request and response schemas of one operation of an OpenAPI specification.
+#[derive(Debug, Clone)]
+pub struct OpenApiContract<'a> {
+    /// The whole specification, against which the `$ref` of the schemas are resolved.
+    document: &'a Value,
+
+    /// Method and path template of the operation, e.g. "POST /activities".
+    pub operation: String,
+
+    /// Schema of the JSON request body, if the operation declares one.
+    request_schema: Option<&'a Value>,
+
+    /// Schemas of the JSON response bodies, keyed by status code ("201", "2XX", "default").
+    response_schemas: Vec<(&'a str, &'a Value)>,
+}
+
+impl OpenApiContract<'_> {
+    /// Checks a request body against the schema of the operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The JSON request body.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<String>` with the schema violations; empty when the operation has no
+    /// request body schema.
+    pub fn check_request(&self, body: &str) -> Vec<String> {
+        match self.request_schema {
+            Some(schema) => self.check(schema, body),
+            None => Vec::new(),
+        }
+    }
+
+    /// Checks a response body against the schema declared for its status code.
+    ///
+    /// The exact status code is looked up first, then its range ("2XX") and then the
+    /// "default" response.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The HTTP status code of the response.
+    /// * `body` - The response body.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<String>` with the schema violations; empty when no JSON schema is declared
+    /// for the status code.
+    pub fn check_response(&self, status: u16, body: &str) -> Vec<String> {
+        let code = status.to_string();
+        let range = format!("{}XX", status / 100);
+        let schema = [code.as_str(), range.as_str(), "default"]
+            .iter()
+            .find_map(|key| {
+                self.response_schemas
+                    .iter()
+                    .find(|(response, _)| response.eq_ignore_ascii_case(key))
+            });
+        match schema {
+            Some((_, schema)) => self.check(schema, body),
+            None => Vec::new(),
+        }
+    }
+
+    fn check(&self, schema: &Value, body: &str) -> Vec<String> {
+        match serde_json::from_str::<Value>(body) {
+            Ok(value) => validate_json_in(self.document, schema, &value),
+            Err(e) => vec![format!("/: the body is not valid JSON: {}", e)],
+        }
+    }
+}
+
+impl OpenApiSpec {
+    /// Loads a JSON OpenAPI specification. YAML specifications are not supported and
+    /// must be converted to JSON first.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the JSON OpenAPI file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<OpenApiSpec, Box<dyn Error>>` with the specification, or an error if it
+    /// is not JSON or has no paths.
+    pub fn load(path: &str) -> Result<OpenApiSpec, Box<dyn Error>> {
+        let data = std::fs::read_to_string(path)?;
+        OpenApiSpec::parse(path, &data)
+    }
+
+    /// Parses a JSON OpenAPI specification read from `source`.
+    fn parse(source: &str, data: &str) -> Result<OpenApiSpec, Box<dyn Error>> {
+        let document: Value = serde_json::from_str(data).map_err(|e| {
+            format!(
+                "The OpenAPI file {} is not valid JSON ({}); only JSON specifications are \
+                 accepted, convert YAML files to JSON first",
+                source, e
+            )
+        })?;
+        if !document.get("paths").is_some_and(Value::is_object) {
+            return Err(format!("The OpenAPI file {} has no paths", source).into());
+        }
+        Ok(OpenApiSpec {
+            source: source.to_string(),
+            document,
+        })
+    }
+
+    /// Finds the contract of the operation a request is sent to.
+    ///
+    /// The operation is found by matching the path of `endpoint` against the path
+    /// templates of the specification (e.g., "/activities/{id}"), after removing the path
+    /// of the first server that prefixes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The URL, or only the path, the request is sent to.
+    /// * `method` - The HTTP method of the request.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<OpenApiContract, Box<dyn Error>>` with the contract, or an error if the
+    /// specification has no operation for the endpoint and method.
+    pub fn contract(
+        &self,
+        endpoint: &str,
+        method: &str,
+    ) -> Result<OpenApiContract<'_>, Box<dyn Error>> {
+        let path = self.source.as_str();
+        let document = &self.document;
+
+        let paths = document
+            .get("paths")
+            .and_then(Value::as_object)
+            .ok_or_else(|| format!("The OpenAPI file {} has no paths", path))?;
+
+        let endpoint_path = url_path(endpoint);
+        let server_paths: Vec<String> = document
+            .get("servers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|server| server.get("url").and_then(Value::as_str))
+            .map(url_path)
+            .collect();
+        let relative_path = server_paths
+            .iter()
+            .filter(|server_path| server_path.as_str() != "/")
+            .find_map(|server_path| endpoint_path.strip_prefix(server_path.trim_end_matches('/')))
+            .filter(|relative| relative.starts_with('/'))
+            .unwrap_or(&endpoint_path);
+
+        let (template, item) = paths
+            .iter()
+            .find(|(template, _)| path_matches(template, relative_path))
+            .ok_or_else(|| {
+                format!(
+                    "The OpenAPI file {} has no path matching {} (paths: {})",
+                    path,
+                    relative_path,
+                    paths.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            })?;
+        let operation = item.get(method.to_lowercase()).ok_or_else(|| {
+            format!(
+                "The OpenAPI file {} has no {} operation for {}",
+                path,
+                method.to_uppercase(),
+                template
+            )
+        })?;
+
+        let request_schema = operation
+            .pointer("/requestBody/content")
+            .and_then(json_schema);
+        let response_schemas = operation
+            .get("responses")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(status, response)| {
+                let schema = response.get("content").and_then(json_schema)?;
+                Some((status.as_str(), schema))
+            })
+            .collect();
+
+        Ok(OpenApiContract {
+            operation: format!("{} {}", method.to_uppercase(), template),
+            document,
+            request_schema,
+            response_schemas,
+        })
+    }
+}
+
+/// Returns the schema of the JSON media type of a `content` object.
+fn json_schema(content: &Value) -> Option<&Value> {
+    let content = content.as_object()?;
+    content
+        .get(JSON_MEDIA_TYPE)
+        .or_else(|| {
+            content
+                .iter()
+                .find(|(media_type, _)| media_type.ends_with("+json"))
+                .map(|(_, media)| media)
+        })?
+        .get("schema")
+}
+
+/// Path of a URL, or the value itself if it is already a path.
+fn url_path(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Returns `true` if a path matches a path template, where each "{parameter}" segment
+/// matches any segment.
+fn path_matches(template: &str, path: &str) -> bool {
+    let template: Vec<&str> = template.trim_end_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    template.len() == path.len()
+        && template.iter().zip(&path).all(|(expected, segment)| {
+            (expected.starts_with('{') && expected.ends_with('}') && !segment.is_empty())
+                || expected == segment
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec() -> OpenApiSpec {
+        let document = json!({
+            "openapi": "3.0.3",
+            "servers": [{ "url": "https://api.example.com/api/v1" }],
+            "paths": {
+                "/activities": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Activity" }
+                                }
+                            }
+                        },
+                        "responses": {
+                            "201": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "object",
+                                            "required": ["id"],
+                                            "properties": { "id": { "type": "string" } }
+                                        }
+                                    }
+                                }
+                            },
+                            "4XX": {
+                                "content": {
+                                    "application/problem+json": {
+                                        "schema": {
+                                            "type": "object",
+                                            "required": ["title"]
+                                        }
+                                    }
+                                }
+                            },
+                            "default": { "description": "Unexpected error" }
+                        }
+                    }
+                },
+                "/activities/{id}": { "put": { "responses": {} } }
+            },
+            "components": {
+                "schemas": {
+                    "Activity": {
+                        "type": "object",
+                        "required": ["id", "golpe"],
+                        "properties": {
+                            "id": { "type": "string" },
+                            "golpe": { "type": "integer", "nullable": true }
+                        }
+                    }
+                }
+            }
+        });
+        OpenApiSpec::parse("openapi.json", &document.to_string()).unwrap()
+    }
+
+    #[test]
+    fn path_templates_match_segments() {
+        assert!(path_matches("/activities", "/activities"));
+        assert!(path_matches("/activities", "/activities/"));
+        assert!(path_matches("/activities/{id}", "/activities/srv-1"));
+        assert!(path_matches(
+            "/strokes/{golpe}/activities/{phase}",
+            "/strokes/1/activities/WARM_UP"
+        ));
+        assert!(!path_matches("/activities/{id}", "/activities"));
+        assert!(!path_matches("/activities/{id}", "/activities//"));
+        assert!(!path_matches("/activities/{id}", "/activities/srv-1/notes"));
+        assert!(!path_matches("/activities", "/sessions"));
+    }
+
+    #[test]
+    fn server_path_is_stripped() {
+        let spec = spec();
+        let contract = spec
+            .contract("https://api.example.com/api/v1/activities", "post")
+            .unwrap();
+        assert_eq!(contract.operation, "POST /activities");
+
+        // Other hosts with the same base path, and plain paths, match too
+        let contract = spec
+            .contract("http://127.0.0.1:8099/api/v1/activities/srv-1", "PUT")
+            .unwrap();
+        assert_eq!(contract.operation, "PUT /activities/{id}");
+        assert!(spec.contract("/activities", "POST").is_ok());
+
+        // A path that only shares a prefix with the server path is not stripped
+        assert!(spec
+            .contract("https://api.example.com/api/v10/activities", "POST")
+            .is_err());
+    }
+
+    #[test]
+    fn unknown_path_or_method_fails() {
+        let spec = spec();
+        let error = spec.contract("/sessions", "POST").unwrap_err();
+        assert!(error.to_string().contains("no path matching /sessions"));
+        let error = spec.contract("/activities", "DELETE").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("no DELETE operation for /activities"));
+    }
+
+    #[test]
+    fn request_body_is_checked() {
+        let spec = spec();
+        let contract = spec.contract("/activities", "POST").unwrap();
+        assert!(contract
+            .check_request(r#"{"id":"D-C1","golpe":null}"#)
+            .is_empty());
+        assert_eq!(
+            contract.check_request(r#"{"id":"D-C1","golpe":"1"}"#),
+            vec!["/golpe: expected integer, found string"]
+        );
+        assert_eq!(
+            contract.check_request(r#"{"golpe":1}"#),
+            vec!["/: missing required property 'id'"]
+        );
+        assert!(contract.check_request("not json")[0].starts_with("/: the body is not valid JSON"));
+
+        // Operations without a request body schema accept any body
+        let contract = spec.contract("/activities/srv-1", "PUT").unwrap();
+        assert!(contract.check_request("{}").is_empty());
+    }
+
+    #[test]
+    fn response_body_is_checked_by_status() {
+        let spec = spec();
+        let contract = spec.contract("/activities", "POST").unwrap();
+        assert!(contract.check_response(201, r#"{"id":"srv-1"}"#).is_empty());
+        assert_eq!(
+            contract.check_response(201, r#"{"id":1}"#),
+            vec!["/id: expected string, found integer"]
+        );
+        // The status range is used when the exact code is not declared, with +json media types
+        assert_eq!(
+            contract.check_response(422, r#"{"detail":"invalid"}"#),
+            vec!["/: missing required property 'title'"]
+        );
+        // The default response declares no schema, so nothing is checked
+        assert!(contract.check_response(500, "Internal error").is_empty());
+        assert!(contract.check_response(200, "anything").is_empty());
+    }
+
+    #[test]
+    fn yaml_and_specs_without_paths_are_rejected() {
+        let error = OpenApiSpec::parse("openapi.yaml", "openapi: 3.0.3\npaths: {}\n").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("only JSON specifications are accepted"));
+        let error = OpenApiSpec::parse("openapi.json", r#"{"openapi":"3.0.3"}"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The OpenAPI file openapi.json has no paths"
+        );
+    }
+}
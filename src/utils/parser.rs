@@ -1,20 +1,50 @@
-use std::collections::HashMap;
+use super::vocabulary::{canonical_term, find_term, Suggestion, TermMatch, VocabularyOptions};
+use crate::models::players::Jugadores;
+use regex::Regex;
+use std::sync::LazyLock;
 
-/// Splits a comma-separated string into a vector of trimmed strings.
+/// Range of player counts at the start of an item (e.g., "2-4 JUGADORES", "3 A 4").
+static PLAYER_RANGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+)\s*(?:-|–|A|AL|HASTA)\s*(\d+)\b").expect("valid regex"));
+
+/// Player count at the start of an item (e.g., "4 JUGADORES").
+static PLAYER_COUNT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+)\b").expect("valid regex"));
+
+/// Splits a multi-value string into a vector of trimmed strings.
 ///
-/// This function takes a string that contains comma-separated values, splits it by commas,
-/// trims any leading or trailing whitespace from each item, and returns a `Vec<String>`
-/// containing the resulting strings.
+/// This function takes a string that contains values separated by any of the given
+/// separators, splits it, trims any leading or trailing whitespace from each item,
+/// drops the empty items and returns a `Vec<String>` containing the resulting strings.
 ///
 /// # Arguments
 ///
-/// * `data` - A string slice (`&str`) that contains comma-separated values.
+/// * `data` - A string slice (`&str`) that contains the separated values.
+/// * `separators` - The separators of the values (e.g., `","` or `" Y "`).
+/// * `dedupe` - Whether to keep only the first occurrence of repeated values.
 ///
 /// # Returns
 ///
 /// A `Vec<String>` containing the individual strings after splitting and trimming.
-pub fn split_to_vec(data: &str) -> Vec<String> {
-    data.split(',').map(|s| s.trim().to_string()).collect()
+pub fn split_to_vec(data: &str, separators: &[String], dedupe: bool) -> Vec<String> {
+    let mut items = vec![data.to_string()];
+    for separator in separators {
+        items = items
+            .iter()
+            .flat_map(|item| item.split(separator.as_str()))
+            .map(str::to_string)
+            .collect();
+    }
+
+    let mut values: Vec<String> = Vec::new();
+    for item in items {
+        let item = item.trim();
+        if item.is_empty() || (dedupe && values.iter().any(|value| value == item)) {
+            continue;
+        }
+        values.push(item.to_string());
+    }
+    values
 }
 
 /// Processes the duration text to transform it into a readable format.
@@ -39,7 +69,38 @@ pub fn process_duration(raw_duration: &str) -> String {
     }
 }
 
-/// Processes the list of player counts, extracting integers from strings like "4 JUGADORES".
+/// Extracts the main duration in minutes from a processed duration string.
+///
+/// # Arguments
+///
+/// * `duration` - A duration as returned by `process_duration`, e.g., "10m - 5m/pareja".
+///
+/// # Returns
+///
+/// An `Option<u32>` with the leading number of minutes (10 in the example), or `None` if there is none.
+pub fn duration_minutes(duration: &str) -> Option<u32> {
+    let digits: String = duration
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Words used in the sheet instead of a player count. They are compared in their
+/// canonical form (see `canonical_term`), so plurals and accents are accepted.
+pub const PLAYER_WORDS: [(&str, i32); 5] = [
+    ("INDIVIDUAL", 1),
+    ("PAREJA", 2),
+    ("DOBLE", 2),
+    ("TRIO", 3),
+    ("CUARTETO", 4),
+];
+
+/// Processes the list of player counts.
+///
+/// Each item may be a single count ("4 JUGADORES"), a range ("2-4 JUGADORES",
+/// "3 A 4") or a word ("PAREJAS", "INDIVIDUAL").
 ///
 /// # Arguments
 ///
@@ -47,17 +108,41 @@ pub fn process_duration(raw_duration: &str) -> String {
 ///
 /// # Returns
 ///
-/// A vector of integers representing the number of players.
-pub fn process_num_jugadores(players: Vec<String>) -> Vec<i32> {
-    players
-        .into_iter()
-        .filter_map(|player| {
-            player
-                .split_whitespace()
-                .next()
-                .and_then(|num| num.parse::<i32>().ok())
-        })
-        .collect()
+/// A tuple with the allowed player counts and the non-empty items that could not be read,
+/// including the counts below 1 or larger than `MAX_PLAYERS`.
+pub fn process_num_jugadores(players: Vec<String>) -> (Jugadores, Vec<String>) {
+    let word_options = VocabularyOptions::default();
+
+    let mut jugadores = Jugadores::default();
+    let mut unreadable = Vec::new();
+    for player in players {
+        let item = player.trim().to_uppercase();
+        let added = if let Some(captures) = PLAYER_RANGE.captures(&item) {
+            match (captures[1].parse::<i32>(), captures[2].parse::<i32>()) {
+                (Ok(first), Ok(last)) => jugadores.add_range(first.min(last), first.max(last)),
+                _ => false,
+            }
+        } else if let Some(captures) = PLAYER_COUNT.captures(&item) {
+            captures[1].parse().is_ok_and(|count| jugadores.add(count))
+        } else if let Some(count) =
+            canonical_term(&item, &word_options)
+                .split(' ')
+                .find_map(|word| {
+                    PLAYER_WORDS
+                        .iter()
+                        .find(|(known, _)| canonical_term(known, &word_options) == word)
+                        .map(|(_, count)| *count)
+                })
+        {
+            jugadores.add(count)
+        } else {
+            item.is_empty()
+        };
+        if !added {
+            unreadable.push(player);
+        }
+    }
+    (jugadores, unreadable)
 }
 
 /// Extracts the number from a string representing a shot.
@@ -76,36 +161,161 @@ pub fn process_golpe(golpe: &str) -> Option<i32> {
         .and_then(|num| num.parse::<i32>().ok())
 }
 
+/// Result of translating a list of terms.
+#[derive(Debug, Default)]
+pub struct Translation {
+    /// Translated values, in the same order as the input. Terms without a translation
+    /// are passed through unchanged.
+    pub values: Vec<String>,
+
+    /// Non-empty terms that have no entry in the translation table, with the closest
+    /// known term when there is one.
+    pub untranslated: Vec<(String, Option<Suggestion>)>,
+
+    /// Unknown terms replaced by the suggested term because its confidence reached the
+    /// auto-correct threshold.
+    pub corrected: Vec<(String, Suggestion)>,
+}
+
+/// Spanish to English translations of the typologies.
+pub const TYPOLOGY_TERMS: [(&str, &str); 5] = [
+    ("INICIACIÓN INFANTIL", "KIDS_BEGINNERS"),
+    ("INICIACIÓN ADULTO", "ADULT_BEGINNERS"),
+    ("PERFECCIONAMIENTO", "IMPROVEMENT"),
+    ("COMPETICIÓN", "TOUR_COMPETITION"),
+    ("PRE-COMPETICIÓN", "PRECOMPETITION"),
+];
+
+/// Spanish to English translations of the levels.
+pub const LEVEL_TERMS: [(&str, &str); 3] = [
+    ("DIFÍCIL", "DIFFICULT"),
+    ("MEDIO", "MEDIUM"),
+    ("FÁCIL", "EASY"),
+];
+
+/// Spanish to English translations of the models.
+pub const MODEL_TERMS: [(&str, &str); 4] = [
+    ("TÉCNICA", "TECHNIQUE"),
+    ("TÁCTICA", "TACTIC"),
+    ("SOCIAL", "SOCIAL"),
+    ("FÍSICO", "PHYSIQUE"),
+];
+
+/// Spanish to English translations of the shots.
+pub const SHOT_TERMS: [(&str, &str); 13] = [
+    ("DERECHA", "Forehand"),
+    ("REVÉS", "Backhand"),
+    ("PARED DE FONDO", "Back Wall"),
+    ("PARED LATERAL REVÉS", "Backhand Side Wall"),
+    ("PARED LATERAL DE DERECHA", "Forehand Side Wall"),
+    ("DOBLE PARED DE REVÉS", "Double Backhand Wall"),
+    ("DOBLE PARED DE DERECHA", "Double Forehand Wall"),
+    ("OVERHEAD", "Overhead"),
+    ("VOLEA", "Volley"),
+    ("GOLPE A LA REJA", "Fence Hit"),
+    ("TRICK SHOT", "Trick Shot"),
+    ("SAQUE", "Serve"),
+    ("RESTO", "Return"),
+];
+
+/// Spanish to English translations of the parts to practice.
+pub const PART_TO_PRACTICE_TERMS: [(&str, &str); 6] = [
+    ("DIRECCIÓN", "DIRECTION"),
+    ("POSICIONAMIENTO", "POSITIONING"),
+    ("ESTRATEGIA", "STRATEGY"),
+    ("POTENCIA", "POWER"),
+    ("PROFUNDIDAD", "DEPTH"),
+    ("ALTURA", "HEIGHT"),
+];
+
+/// Spanish to English translations of the materials.
+pub const MATERIAL_TERMS: [(&str, &str); 9] = [
+    ("CONOS", "CONES"),
+    ("LÍNEAS DE SEÑALIZACIÓN AMARILLAS", "YELLOW_MARKING_LINES"),
+    ("CONOS GRANDES", "LARGE_CONES"),
+    ("ESCALERA DE COORDINACIÓN", "COORDINATION_LADDER"),
+    ("RIÑONERA DE PELOTAS", "BALL_BELT"),
+    ("AROS", "HOOPS"),
+    ("MINI RED", "MINI_NET"),
+    ("PICKIS RECOGE BOLAS", "BALL_PICKER_PICKIS"),
+    ("MINI PORTERIA", "MINI_GOAL"),
+];
+
+/// Translates a list of terms using a translation table.
+///
+/// Terms are matched ignoring case, spacing, plurals and, optionally, accents. Terms
+/// that are already a translated value of the table (e.g., "EASY") are kept and not
+/// reported as untranslated. Unknown terms close to a known one are replaced by it
+/// when auto-correct is enabled and the confidence is high enough.
+///
+/// # Arguments
+///
+/// * `items` - The terms to translate.
+/// * `terms` - The translation table, as (Spanish, English) pairs.
+/// * `uppercase` - Whether to convert the translated values to uppercase.
+/// * `options` - The vocabulary matching settings.
+///
+/// # Returns
+///
+/// A `Translation` with the translated values and the terms that could not be translated.
+fn translate_terms(
+    items: Vec<String>,
+    terms: &[(&str, &str)],
+    uppercase: bool,
+    options: &VocabularyOptions,
+) -> Translation {
+    let mut translation = Translation::default();
+
+    for item in items {
+        let value = match find_term(&item, terms, options) {
+            TermMatch::Known(translated) | TermMatch::Translated(translated) => translated,
+            TermMatch::Nearest(suggestion) => {
+                let autocorrect = options
+                    .autocorrect_threshold
+                    .is_some_and(|threshold| suggestion.confidence >= threshold);
+                if autocorrect {
+                    let translated = suggestion.translation.clone();
+                    translation.corrected.push((item, suggestion));
+                    translated
+                } else {
+                    translation
+                        .untranslated
+                        .push((item.clone(), Some(suggestion)));
+                    item
+                }
+            }
+            TermMatch::Unknown => {
+                if !item.is_empty() {
+                    translation.untranslated.push((item.clone(), None));
+                }
+                item
+            }
+        };
+        translation.values.push(if uppercase {
+            value.to_uppercase()
+        } else {
+            value
+        });
+    }
+
+    translation
+}
+
 /// Translates typology strings from Spanish to English.
 ///
 /// # Arguments
 ///
 /// * `typology` - A vector of strings in Spanish representing typologies.
+/// * `options` - The vocabulary matching settings.
 ///
 /// # Returns
 ///
-/// A vector of translated typologies in English.
-pub fn translate_typology_to_english(typology: Vec<String>) -> Vec<String> {
-    let translation_map: HashMap<&str, &str> = [
-        ("INICIACIÓN INFANTIL", "KIDS_BEGINNERS"),
-        ("INICIACIÓN ADULTO", "ADULT_BEGINNERS"),
-        ("PERFECCIONAMIENTO", "IMPROVEMENT"),
-        ("COMPETICIÓN", "TOUR_COMPETITION"),
-        ("PRE-COMPETICIÓN", "PRECOMPETITION"),
-    ]
-    .iter()
-    .cloned()
-    .collect();
-
-    typology
-        .into_iter()
-        .map(|item| {
-            translation_map
-                .get(item.as_str())
-                .unwrap_or(&item.as_str())
-                .to_string()
-        })
-        .collect()
+/// A `Translation` with the translated typologies in English.
+pub fn translate_typology_to_english(
+    typology: Vec<String>,
+    options: &VocabularyOptions,
+) -> Translation {
+    translate_terms(typology, &TYPOLOGY_TERMS, false, options)
 }
 
 /// Translates level strings from Spanish to English.
@@ -113,29 +323,13 @@ pub fn translate_typology_to_english(typology: Vec<String>) -> Vec<String> {
 /// # Arguments
 ///
 /// * `level` - A vector of strings in Spanish representing levels.
+/// * `options` - The vocabulary matching settings.
 ///
 /// # Returns
 ///
-/// A vector of translated levels in English.
-pub fn translate_level_to_english(level: Vec<String>) -> Vec<String> {
-    let translation_map: HashMap<&str, &str> = [
-        ("DIFÍCIL", "DIFFICULT"),
-        ("MEDIO", "MEDIUM"),
-        ("FÁCIL", "EASY"),
-    ]
-    .iter()
-    .cloned()
-    .collect();
-
-    level
-        .into_iter()
-        .map(|item| {
-            translation_map
-                .get(item.as_str())
-                .unwrap_or(&item.as_str())
-                .to_string()
-        })
-        .collect()
+/// A `Translation` with the translated levels in English.
+pub fn translate_level_to_english(level: Vec<String>, options: &VocabularyOptions) -> Translation {
+    translate_terms(level, &LEVEL_TERMS, false, options)
 }
 
 /// Translates model strings from Spanish to English.
@@ -143,30 +337,13 @@ pub fn translate_level_to_english(level: Vec<String>) -> Vec<String> {
 /// # Arguments
 ///
 /// * `model` - A vector of strings in Spanish representing models.
+/// * `options` - The vocabulary matching settings.
 ///
 /// # Returns
 ///
-/// A vector of translated models in English.
-pub fn translate_model_to_english(model: Vec<String>) -> Vec<String> {
-    let translation_map: HashMap<&str, &str> = [
-        ("TÉCNICA", "TECHNIQUE"),
-        ("TÁCTICA", "TACTIC"),
-        ("SOCIAL", "SOCIAL"),
-        ("FÍSICO", "PHYSIQUE"),
-    ]
-    .iter()
-    .cloned()
-    .collect();
-
-    model
-        .into_iter()
-        .map(|item| {
-            translation_map
-                .get(item.as_str())
-                .unwrap_or(&item.as_str())
-                .to_string()
-        })
-        .collect()
+/// A `Translation` with the translated models in English.
+pub fn translate_model_to_english(model: Vec<String>, options: &VocabularyOptions) -> Translation {
+    translate_terms(model, &MODEL_TERMS, false, options)
 }
 
 /// Translates shot strings from Spanish to English and converts them to uppercase.
@@ -174,38 +351,13 @@ pub fn translate_model_to_english(model: Vec<String>) -> Vec<String> {
 /// # Arguments
 ///
 /// * `shot` - A vector of strings in Spanish representing shots.
+/// * `options` - The vocabulary matching settings.
 ///
 /// # Returns
 ///
-/// A vector of translated shots in English (uppercase).
-pub fn translate_shot_to_english(shot: Vec<String>) -> Vec<String> {
-    let translation_map: HashMap<&str, &str> = [
-        ("DERECHA", "Forehand"),
-        ("REVÉS", "Backhand"),
-        ("PARED DE FONDO", "Back Wall"),
-        ("PARED LATERAL REVÉS", "Backhand Side Wall"),
-        ("PARED LATERAL DE DERECHA", "Forehand Side Wall"),
-        ("DOBLE PARED DE REVÉS", "Double Backhand Wall"),
-        ("DOBLE PARED DE DERECHA", "Double Forehand Wall"),
-        ("OVERHEAD", "Overhead"),
-        ("VOLEA", "Volley"),
-        ("GOLPE A LA REJA", "Fence Hit"),
-        ("TRICK SHOT", "Trick Shot"),
-        ("SAQUE", "Serve"),
-        ("RESTO", "Return"),
-    ]
-    .iter()
-    .cloned()
-    .collect();
-
-    shot.into_iter()
-        .map(|item| {
-            translation_map
-                .get(item.as_str())
-                .unwrap_or(&item.as_str())
-                .to_uppercase()
-        })
-        .collect()
+/// A `Translation` with the translated shots in English (uppercase).
+pub fn translate_shot_to_english(shot: Vec<String>, options: &VocabularyOptions) -> Translation {
+    translate_terms(shot, &SHOT_TERMS, true, options)
 }
 
 /// Translates practice part strings from Spanish to English and converts them to uppercase.
@@ -213,31 +365,13 @@ pub fn translate_shot_to_english(shot: Vec<String>) -> Vec<String> {
 /// # Arguments
 ///
 /// * `part` - A vector of strings in Spanish representing parts to practice.
+/// * `options` - The vocabulary matching settings.
 ///
 /// # Returns
 ///
-/// A vector of translated parts to practice in English (uppercase).
-pub fn translate_part_to_practice(part: Vec<String>) -> Vec<String> {
-    let translation_map: HashMap<&str, &str> = [
-        ("DIRECCIÓN", "DIRECTION"),
-        ("POSICIONAMIENTO", "POSITIONING"),
-        ("ESTRATEGIA", "STRATEGY"),
-        ("POTENCIA", "POWER"),
-        ("PROFUNDIDAD", "DEPTH"),
-        ("ALTURA", "HEIGHT"),
-    ]
-    .iter()
-    .cloned()
-    .collect();
-
-    part.into_iter()
-        .map(|item| {
-            translation_map
-                .get(item.as_str())
-                .unwrap_or(&item.as_str())
-                .to_uppercase()
-        })
-        .collect()
+/// A `Translation` with the translated parts to practice in English (uppercase).
+pub fn translate_part_to_practice(part: Vec<String>, options: &VocabularyOptions) -> Translation {
+    translate_terms(part, &PART_TO_PRACTICE_TERMS, true, options)
 }
 
 /// Translates material strings from Spanish to English and converts them to uppercase.
@@ -245,33 +379,99 @@ pub fn translate_part_to_practice(part: Vec<String>) -> Vec<String> {
 /// # Arguments
 ///
 /// * `material` - A vector of strings in Spanish representing materials.
+/// * `options` - The vocabulary matching settings.
 ///
 /// # Returns
 ///
-/// A vector of translated materials in English (uppercase).
-pub fn translate_material(material: Vec<String>) -> Vec<String> {
-    let translation_map: HashMap<&str, &str> = [
-        ("CONOS", "CONES"),
-        ("LÍNEAS DE SEÑALIZACIÓN AMARILLAS", "YELLOW_MARKING_LINES"),
-        ("CONOS GRANDES", "LARGE_CONES"),
-        ("ESCALERA DE COORDINACIÓN", "COORDINATION_LADDER"),
-        ("RIÑONERA DE PELOTAS", "BALL_BELT"),
-        ("AROS", "HOOPS"),
-        ("MINI RED", "MINI_NET"),
-        ("PICKIS RECOGE BOLAS", "BALL_PICKER_PICKIS"),
-        ("MINI PORTERIA", "MINI_GOAL"),
-    ]
-    .iter()
-    .cloned()
-    .collect();
-
-    material
-        .into_iter()
-        .map(|item| {
-            translation_map
-                .get(item.as_str())
-                .unwrap_or(&item.as_str())
-                .to_uppercase()
-        })
-        .collect()
+/// A `Translation` with the translated materials in English (uppercase).
+pub fn translate_material(material: Vec<String>, options: &VocabularyOptions) -> Translation {
+    translate_terms(material, &MATERIAL_TERMS, true, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::players::MAX_PLAYERS;
+
+    fn items(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn counts(values: &[&str]) -> (Vec<i32>, Vec<String>) {
+        let (jugadores, unreadable) = process_num_jugadores(items(values));
+        (jugadores.iter().collect(), unreadable)
+    }
+
+    #[test]
+    fn reads_single_counts() {
+        assert_eq!(counts(&["4 JUGADORES"]), (vec![4], vec![]));
+        assert_eq!(counts(&["1", " 6 jugadores "]), (vec![1, 6], vec![]));
+    }
+
+    #[test]
+    fn reads_ranges() {
+        assert_eq!(counts(&["2-4 JUGADORES"]), (vec![2, 3, 4], vec![]));
+        assert_eq!(counts(&["3 A 4"]), (vec![3, 4], vec![]));
+        assert_eq!(counts(&["4 HASTA 2"]), (vec![2, 3, 4], vec![]));
+        assert_eq!(counts(&["1 – 2", "2-3"]), (vec![1, 2, 3], vec![]));
+    }
+
+    #[test]
+    fn reads_words_in_singular_and_plural() {
+        assert_eq!(counts(&["INDIVIDUAL"]), (vec![1], vec![]));
+        assert_eq!(counts(&["PAREJA", "PAREJAS"]), (vec![2], vec![]));
+        assert_eq!(counts(&["DOBLE"]), (vec![2], vec![]));
+        assert_eq!(counts(&["DOBLES"]), (vec![2], vec![]));
+        assert_eq!(counts(&["TRÍOS"]), (vec![3], vec![]));
+        assert_eq!(counts(&["EN CUARTETOS"]), (vec![4], vec![]));
+        assert_eq!(counts(&["VARIOS"]), (vec![], items(&["VARIOS"])));
+        assert_eq!(counts(&["", "  "]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn rejects_zero_players() {
+        assert_eq!(
+            counts(&["0 JUGADORES", "0-2", "2"]),
+            (vec![2], items(&["0 JUGADORES", "0-2"]))
+        );
+    }
+
+    #[test]
+    fn autocorrects_from_the_threshold() {
+        let translate = |threshold| {
+            translate_typology_to_english(
+                items(&["PERFECIONAMIENTO"]),
+                &VocabularyOptions {
+                    autocorrect_threshold: threshold,
+                    ..VocabularyOptions::default()
+                },
+            )
+        };
+
+        let corrected = translate(Some(0.9));
+        assert_eq!(corrected.values, vec!["IMPROVEMENT"]);
+        assert_eq!(corrected.corrected[0].1.term, "PERFECCIONAMIENTO");
+        assert!(corrected.untranslated.is_empty());
+
+        for threshold in [Some(0.95), None] {
+            let reported = translate(threshold);
+            assert_eq!(reported.values, vec!["PERFECIONAMIENTO"]);
+            assert!(reported.corrected.is_empty());
+            let suggestion = reported.untranslated[0].1.as_ref().unwrap();
+            assert_eq!(suggestion.term, "PERFECCIONAMIENTO");
+        }
+    }
+
+    #[test]
+    fn rejects_counts_beyond_the_bound() {
+        let huge_range = format!("1-{}", i32::MAX);
+        let huge_count = format!("{} JUGADORES", MAX_PLAYERS + 1);
+        let (jugadores, unreadable) =
+            process_num_jugadores(items(&["2-4", &huge_range, &huge_count, "99999999999"]));
+        assert_eq!(jugadores.iter().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(
+            unreadable,
+            items(&[&huge_range, &huge_count, "99999999999"])
+        );
+    }
 }
@@ -0,0 +1,347 @@
+use super::mapping::PayloadMapping;
+use super::parser::duration_minutes;
+use super::schema::{payload_schema, validate_json, SchemaDocument};
+use crate::models::{activity::Actividad, phase::PhaseFormat, session::SessionDocument};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Version of the payload contract shared with the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PayloadVersion {
+    /// Free-text lists and the duration as written in the sheet.
+    #[default]
+    V1,
+    /// Lists restricted to the known English terms, and the duration as an object with
+    /// the total minutes and the original text.
+    V2,
+}
+
+impl FromStr for PayloadVersion {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "v1" | "1" => Ok(PayloadVersion::V1),
+            "v2" | "2" => Ok(PayloadVersion::V2),
+            other => Err(format!("Unknown payload version: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for PayloadVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadVersion::V1 => write!(f, "v1"),
+            PayloadVersion::V2 => write!(f, "v2"),
+        }
+    }
+}
+
+/// Settings that control how activities are written in the API payload.
+#[derive(Debug, Default, Clone)]
+pub struct PayloadOptions {
+    /// Whether to include the workbook location of each activity.
+    pub include_provenance: bool,
+
+    /// How the phase of each activity is written.
+    pub phase_format: PhaseFormat,
+
+    /// Version of the payload contract.
+    pub version: PayloadVersion,
+
+    /// Mapping applied to each activity to match the schema of the API.
+    pub mapping: Option<PayloadMapping>,
+}
+
+/// Serializes an activity into the JSON body sent to the API.
+///
+/// The phase is written in the configured format. The provenance of the activity is
+/// left out of the payload unless `include_provenance` is set, in which case it is
+/// added under the "provenance" key. The payload is checked against the schema of the
+/// configured version, and the payload mapping, if any, is applied last.
+///
+/// # Arguments
+///
+/// * `actividad` - The activity to serialize.
+/// * `options` - The payload settings.
+/// * `schema` - The activity schema of `options`, built once with `payload_schema`.
+///
+/// # Returns
+///
+/// A `Result<String, Box<dyn Error>>` with the JSON payload, or an error listing the
+/// schema violations.
+pub fn actividad_payload(
+    actividad: &Actividad,
+    options: &PayloadOptions,
+    schema: &Value,
+) -> Result<String, Box<dyn Error>> {
+    Ok(serde_json::to_string(&actividad_value(
+        actividad, options, schema,
+    )?)?)
+}
+
+/// Serializes a session document into the JSON body sent to the API.
+///
+/// The activities of each phase are written as in `actividad_payload`, and the phases
+/// in the same format as the phase of the activities. The whole document is checked
+/// against the session schema of the configured version, and the payload mapping, if
+/// any, is then applied to each activity.
+///
+/// # Arguments
+///
+/// * `document` - The session document to serialize.
+/// * `options` - The payload settings.
+///
+/// # Returns
+///
+/// A `Result<String, Box<dyn Error>>` with the JSON payload, or an error listing the
+/// schema violations.
+pub fn session_payload(
+    document: &SessionDocument,
+    options: &PayloadOptions,
+) -> Result<String, Box<dyn Error>> {
+    let mut payload = serde_json::to_value(document)?;
+
+    if let Some(phases) = payload.get_mut("phases").and_then(Value::as_array_mut) {
+        for (value, phase) in phases.iter_mut().zip(&document.phases) {
+            let activities = phase
+                .activities
+                .iter()
+                .map(|actividad| unmapped_value(actividad, options))
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(object) = value.as_object_mut() {
+                object.insert(
+                    "phase".to_string(),
+                    phase.phase.to_wire(options.phase_format),
+                );
+                object.insert("activities".to_string(), Value::Array(activities));
+            }
+        }
+    }
+
+    check_schema(
+        "session",
+        &payload_schema(SchemaDocument::Session, options),
+        &payload,
+        options,
+    )?;
+
+    if let Some(mapping) = &options.mapping {
+        let activities = payload
+            .get_mut("phases")
+            .and_then(Value::as_array_mut)
+            .into_iter()
+            .flatten()
+            .filter_map(|phase| phase.get_mut("activities").and_then(Value::as_array_mut))
+            .flatten();
+        for activity in activities {
+            *activity = mapping.apply(activity.take())?;
+        }
+    }
+
+    Ok(serde_json::to_string(&payload)?)
+}
+
+/// Serializes an activity, writing the phase, the provenance and the duration as configured,
+/// then checks it against the schema and applies the mapping.
+///
+/// # Arguments
+///
+/// * `actividad` - The activity to serialize.
+/// * `options` - The payload settings.
+/// * `schema` - The activity schema of `options`, built once with `payload_schema`.
+///
+/// # Returns
+///
+/// A `Result<Value, Box<dyn Error>>` with the payload, as sent by `actividad_payload`.
+pub fn actividad_value(
+    actividad: &Actividad,
+    options: &PayloadOptions,
+    schema: &Value,
+) -> Result<Value, Box<dyn Error>> {
+    let payload = unmapped_value(actividad, options)?;
+    check_schema("activity", schema, &payload, options)?;
+
+    match &options.mapping {
+        Some(mapping) => mapping.apply(payload),
+        None => Ok(payload),
+    }
+}
+
+/// Serializes an activity, writing the phase, the provenance and the duration as
+/// configured, before the mapping.
+fn unmapped_value(
+    actividad: &Actividad,
+    options: &PayloadOptions,
+) -> Result<Value, Box<dyn Error>> {
+    let mut payload = serde_json::to_value(actividad)?;
+
+    if let Some(object) = payload.as_object_mut() {
+        object.insert(
+            "phase".to_string(),
+            actividad.phase.to_wire(options.phase_format),
+        );
+        if let (true, Some(provenance)) = (options.include_provenance, &actividad.provenance) {
+            object.insert("provenance".to_string(), serde_json::to_value(provenance)?);
+        }
+        if options.version == PayloadVersion::V2 {
+            object.insert(
+                "duration".to_string(),
+                json!({
+                    "minutes": duration_minutes(&actividad.duration),
+                    "text": actividad.duration,
+                }),
+            );
+        }
+    }
+
+    Ok(payload)
+}
+
+/// Checks a payload against its schema.
+///
+/// # Returns
+///
+/// A `Result<(), Box<dyn Error>>` with an error listing the violations, if any.
+fn check_schema(
+    document: &str,
+    schema: &Value,
+    payload: &Value,
+    options: &PayloadOptions,
+) -> Result<(), Box<dyn Error>> {
+    let violations = validate_json(schema, payload);
+    if !violations.is_empty() {
+        return Err(format!(
+            "The {} payload does not match the {} schema: {}",
+            document,
+            options.version,
+            violations.join("; ")
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Describes an activity for logs and error messages, including its workbook location when known.
+pub fn describe_actividad(actividad: &Actividad) -> String {
+    match &actividad.provenance {
+        Some(provenance) => format!("{} ({})", actividad.id, provenance),
+        None => actividad.id.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::phase::Phase;
+    use crate::models::session::{SessionPhase, SessionTotals};
+
+    fn actividad(id: &str) -> Actividad {
+        serde_json::from_value(json!({
+            "id": id,
+            "golpe": 1,
+            "phase": "WARM_UP",
+            "num_jugadores": [2, 4],
+            "typology": ["IMPROVEMENT"],
+            "level": ["EASY"],
+            "model": ["TECHNIQUE"],
+            "shot": ["FOREHAND"],
+            "part_to_practice": ["DEPTH"],
+            "equipment": ["CONES"],
+            "duration": "10m",
+            "content": {
+                "ES": { "title": "Calentar", "goal": "Activar", "script": "Pelotear suave" }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn session(actividades: Vec<Actividad>) -> SessionDocument {
+        SessionDocument {
+            golpe: None,
+            phases: vec![SessionPhase {
+                phase: Phase::WarmUp,
+                duration_minutes: 10,
+                activities: actividades,
+            }],
+            totals: SessionTotals {
+                activities: 1,
+                duration_minutes: 10,
+                ..SessionTotals::default()
+            },
+        }
+    }
+
+    #[test]
+    fn writes_the_session_in_the_configured_version() {
+        let options = PayloadOptions {
+            version: PayloadVersion::V2,
+            ..PayloadOptions::default()
+        };
+        let payload: Value = serde_json::from_str(
+            &session_payload(&session(vec![actividad("D-C1")]), &options).unwrap(),
+        )
+        .unwrap();
+        let activity = &payload["phases"][0]["activities"][0];
+        assert_eq!(
+            activity["duration"],
+            json!({ "minutes": 10, "text": "10m" })
+        );
+        assert_eq!(payload["phases"][0]["phase"], json!("WARM_UP"));
+    }
+
+    #[test]
+    fn rejects_a_session_that_does_not_match_its_schema() {
+        let mut document = session(vec![actividad("D-C1")]);
+        document.phases[0].activities[0].id = String::new();
+        let error = session_payload(&document, &PayloadOptions::default()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("The session payload does not match the v1 schema"),
+            "{}",
+            error
+        );
+        assert!(
+            error.to_string().contains("/phases/0/activities/0/id"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn maps_the_session_activities_after_checking_the_schema() {
+        let options = PayloadOptions {
+            mapping: Some(
+                serde_json::from_value(json!({ "fields": { "num_jugadores": "players" } }))
+                    .unwrap(),
+            ),
+            ..PayloadOptions::default()
+        };
+        let payload: Value = serde_json::from_str(
+            &session_payload(&session(vec![actividad("D-C1")]), &options).unwrap(),
+        )
+        .unwrap();
+        let activity = &payload["phases"][0]["activities"][0];
+        assert_eq!(activity["players"], json!([2, 4]));
+        assert!(activity.get("num_jugadores").is_none());
+    }
+
+    #[test]
+    fn checks_each_activity_against_the_given_schema() {
+        let options = PayloadOptions::default();
+        let schema = payload_schema(SchemaDocument::Activity, &options);
+        assert!(actividad_payload(&actividad("D-C1"), &options, &schema).is_ok());
+
+        let error = actividad_payload(&actividad(""), &options, &schema).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("The activity payload does not match the v1 schema"),
+            "{}",
+            error
+        );
+    }
+}
@@ -1,27 +1,839 @@
 // utils/post_request.rs
-use reqwest::blocking::Client;
+use super::signing::RequestSigner;
+use super::throttle::{CircuitBreaker, CircuitBreakerSettings, RateLimit, TokenBucket};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::{Certificate, Identity, Method, Proxy, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
 
-/// Sends a POST request with activity data.
-///
-/// # Arguments
-/// * `actividad_json` - The activity data in JSON format.
-/// * `endpoint` - The URL of the API endpoint.
-///
-/// # Returns
-/// * `Result<String, Box<dyn Error>>` - The response from the server or an error.
-pub fn post_request(actividad_json: &str, endpoint: &str) -> Result<String, Box<dyn Error>> {
-    let client = Client::new();
-    let response = client
-        .post(endpoint)
-        .header("Content-Type", "application/json")
-        .body(actividad_json.to_string())
-        .send()?;
-
-    if response.status().is_success() {
-        let body = response.text()?;
-        Ok(body)
-    } else {
-        Err(format!("Error al enviar la actividad: {}", response.status()).into())
+/// User agent sent when the settings give none, e.g. "excel_reader/0.1.0".
+pub const DEFAULT_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Successful response of the API.
+#[derive(Debug)]
+pub struct ApiResponse {
+    /// HTTP status code.
+    pub status: u16,
+
+    /// Response body.
+    pub body: String,
+}
+
+impl fmt::Display for ApiResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.body)
+    }
+}
+
+/// Response of the API with an error status code.
+#[derive(Debug)]
+pub struct HttpStatusError {
+    /// HTTP status code.
+    pub status: u16,
+
+    /// Response body, usually describing the error.
+    pub body: String,
+}
+
+impl HttpStatusError {
+    /// Returns `true` if the server rejected the content of the request body (400 Bad
+    /// Request, 413 Payload Too Large or 422 Unprocessable Entity), so that a smaller body
+    /// may be accepted.
+    pub fn is_rejection(&self) -> bool {
+        matches!(self.status, 400 | 413 | 422)
+    }
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match StatusCode::from_u16(self.status) {
+            Ok(status) => write!(f, "Error al enviar la actividad: {}", status),
+            Err(_) => write!(f, "Error al enviar la actividad: {}", self.status),
+        }
+    }
+}
+
+impl Error for HttpStatusError {}
+
+/// When and how often a failed request is sent again.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts of each request, including the first one.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Wait before the first retry, in milliseconds; it doubles after each attempt.
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+
+    /// Status codes that are retried. Connection errors and timeouts are always retried.
+    #[serde(default = "default_retry_statuses")]
+    pub retry_statuses: Vec<u16>,
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+
+fn default_backoff_ms() -> u64 {
+    500
+}
+
+fn default_retry_statuses() -> Vec<u16> {
+    vec![429, 502, 503, 504]
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            retry_statuses: default_retry_statuses(),
+        }
+    }
+}
+
+/// Bodies smaller than this are sent uncompressed, as compressing them saves little.
+pub const DEFAULT_COMPRESSION_MIN_BYTES: usize = 1024;
+
+/// Content-Encoding of the request bodies.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEncoding {
+    /// gzip format.
+    Gzip,
+    /// zlib format, named "deflate" in HTTP.
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Value of the Content-Encoding header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+
+    /// Compresses a body.
+    fn encode(&self, body: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match self {
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+            ContentEncoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+        })
+    }
+}
+
+/// Sizes of the request bodies sent compressed during a run.
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+pub struct CompressionStats {
+    /// Number of request bodies sent compressed.
+    pub requests: usize,
+
+    /// Size of those bodies before compression, in bytes.
+    pub original_bytes: usize,
+
+    /// Size of those bodies after compression, in bytes.
+    pub compressed_bytes: usize,
+}
+
+impl CompressionStats {
+    /// Bytes saved by the compression.
+    pub fn saved_bytes(&self) -> usize {
+        self.original_bytes.saturating_sub(self.compressed_bytes)
+    }
+}
+
+impl fmt::Display for CompressionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percentage = match self.original_bytes {
+            0 => 0.0,
+            original => self.saved_bytes() as f64 * 100.0 / original as f64,
+        };
+        write!(
+            f,
+            "{} request bodies compressed: {} bytes sent instead of {} ({:.1}% saved)",
+            self.requests, self.compressed_bytes, self.original_bytes, percentage
+        )
+    }
+}
+
+/// HTTP proxy the requests go through.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ProxySettings {
+    /// URL of the proxy, e.g. "http://proxy.club.local:3128".
+    pub url: String,
+
+    /// Comma-separated hosts, domains or IP ranges reached without the proxy, e.g.
+    /// "localhost,.club.local,10.0.0.0/8".
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+
+    /// User name of the proxy, if it needs authentication.
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Environment variable holding the password of the proxy user.
+    #[serde(default)]
+    pub password_variable: Option<String>,
+}
+
+/// Certificates used in the TLS connections.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TlsSettings {
+    /// PEM files with extra root certificates, e.g. the private CA of a staging server.
+    #[serde(default)]
+    pub ca_certificates: Vec<String>,
+
+    /// PEM file with the client certificate chain, for servers requiring mutual TLS.
+    #[serde(default)]
+    pub client_certificate: Option<String>,
+
+    /// PEM file with the PKCS#8 private key of the client certificate.
+    #[serde(default)]
+    pub client_key: Option<String>,
+}
+
+/// Settings of the HTTP client shared by every request of a run.
+#[derive(Debug, Clone, Default)]
+pub struct ClientSettings {
+    /// Headers added to every request, e.g. the credentials.
+    pub headers: Vec<(String, String)>,
+
+    /// Total time allowed for each request, from connecting until the whole response body
+    /// is read.
+    pub timeout: Option<Duration>,
+
+    /// Maximum duration of the connection to the server.
+    pub connect_timeout: Option<Duration>,
+
+    /// Retry policy of the failed requests.
+    pub retry: RetryPolicy,
+
+    /// Proxy the requests go through; the proxy environment variables are used when missing.
+    pub proxy: Option<ProxySettings>,
+
+    /// Extra root certificates and the client certificate.
+    pub tls: TlsSettings,
+
+    /// User agent of the requests, `DEFAULT_USER_AGENT` when missing.
+    pub user_agent: Option<String>,
+
+    /// Signing step run on every request, e.g. an HMAC of the body.
+    pub signer: Option<Arc<dyn RequestSigner>>,
+
+    /// Content-Encoding of the request bodies, sent uncompressed when missing.
+    pub compression: Option<ContentEncoding>,
+
+    /// Size from which the request bodies are compressed, in bytes.
+    pub compression_min_bytes: usize,
+
+    /// Maximum request rate, unlimited when missing.
+    pub rate_limit: Option<RateLimit>,
+
+    /// Circuit breaker stopping the requests while the server is failing.
+    pub circuit_breaker: Option<CircuitBreakerSettings>,
+}
+
+/// HTTP client used to talk to the API.
+#[derive(Debug)]
+pub struct ApiClient {
+    client: Client,
+    headers: Vec<(String, String)>,
+    retry: RetryPolicy,
+    signer: Option<Arc<dyn RequestSigner>>,
+    compression: Cell<Option<ContentEncoding>>,
+    compression_min_bytes: usize,
+    compression_stats: Cell<CompressionStats>,
+    rate_limiter: Option<TokenBucket>,
+    circuit_breaker: Option<CircuitBreaker>,
+    retries: Cell<usize>,
+}
+
+impl ApiClient {
+    /// Builds a client with the given settings.
+    ///
+    /// # Arguments
+    /// * `settings` - The headers, timeouts, retry policy, proxy and certificates.
+    ///
+    /// # Returns
+    /// * `Result<ApiClient, Box<dyn Error>>` - The client, or an error if a certificate or
+    ///   the proxy is invalid.
+    pub fn new(settings: ClientSettings) -> Result<ApiClient, Box<dyn Error>> {
+        let mut builder = Client::builder()
+            .user_agent(settings.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+        if let Some(timeout) = settings.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = settings.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = &settings.proxy {
+            builder = builder.proxy(build_proxy(proxy)?);
+        }
+        for path in &settings.tls.ca_certificates {
+            let certificates = Certificate::from_pem_bundle(&read_pem(path)?)
+                .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+            if certificates.is_empty() {
+                return Err(format!("The CA certificate file {} has no certificates", path).into());
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        match (&settings.tls.client_certificate, &settings.tls.client_key) {
+            (Some(certificate), Some(key)) => {
+                let identity = Identity::from_pkcs8_pem(&read_pem(certificate)?, &read_pem(key)?)
+                    .map_err(|e| {
+                    format!("Invalid client certificate {}: {}", certificate, e)
+                })?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err("The client certificate and its key must be given together".into());
+            }
+        }
+        Ok(ApiClient {
+            client: builder.build()?,
+            headers: settings.headers,
+            retry: settings.retry,
+            signer: settings.signer,
+            compression: Cell::new(settings.compression),
+            compression_min_bytes: settings.compression_min_bytes,
+            compression_stats: Cell::new(CompressionStats::default()),
+            rate_limiter: settings
+                .rate_limit
+                .as_ref()
+                .map(TokenBucket::new)
+                .transpose()?,
+            circuit_breaker: settings.circuit_breaker.map(CircuitBreaker::new),
+            retries: Cell::new(0),
+        })
+    }
+
+    /// Returns the number of requests sent again after a failed attempt so far.
+    pub fn retries(&self) -> usize {
+        self.retries.get()
+    }
+
+    /// Returns the sizes of the request bodies sent compressed so far.
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.compression_stats.get()
+    }
+
+    /// Sends a request with activity data, such as a POST creating an activity or a PUT
+    /// updating an activity already on the server.
+    ///
+    /// # Arguments
+    /// * `method` - The HTTP method.
+    /// * `actividad_json` - The request body in JSON format.
+    /// * `endpoint` - The URL of the API endpoint.
+    ///
+    /// # Returns
+    /// * `Result<ApiResponse, Box<dyn Error>>` - The response from the server or an error; an
+    ///   error status code is returned as an `HttpStatusError`.
+    pub fn send(
+        &self,
+        method: Method,
+        actividad_json: &str,
+        endpoint: &str,
+    ) -> Result<ApiResponse, Box<dyn Error>> {
+        self.execute(&method, endpoint, Some(actividad_json), || {
+            self.client
+                .request(method.clone(), endpoint)
+                .header("Content-Type", "application/json")
+        })
+    }
+
+    /// Sends a GET request expecting a JSON response.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The URL of the API endpoint.
+    ///
+    /// # Returns
+    /// * `Result<ApiResponse, Box<dyn Error>>` - The response from the server or an error; an
+    ///   error status code is returned as an `HttpStatusError`.
+    pub fn get(&self, endpoint: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        self.execute(&Method::GET, endpoint, None, || {
+            self.client
+                .get(endpoint)
+                .header("Accept", "application/json")
+        })
+    }
+
+    /// Sends a request, compressing, signing, throttling and retrying it as configured. Each
+    /// attempt is signed again; the signature covers the uncompressed body. Once the circuit
+    /// breaker stopped sending, the request fails with a `DeferredError`.
+    ///
+    /// A server answering 415 (Unsupported Media Type) to a compressed body gets it again
+    /// uncompressed, and compression stays off for the rest of the run.
+    fn execute(
+        &self,
+        method: &Method,
+        endpoint: &str,
+        body: Option<&str>,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<ApiResponse, Box<dyn Error>> {
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.before_request()?;
+        }
+        let mut attempt = 1;
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire();
+            }
+            let mut builder = request();
+            for (name, value) in &self.headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(signer) = &self.signer {
+                for (name, value) in signer.sign(method, endpoint, body.unwrap_or_default())? {
+                    builder = builder.header(name, value);
+                }
+            }
+            let mut compressed = None;
+            if let Some(body) = body {
+                builder = match self.compression.get() {
+                    Some(encoding) if body.len() >= self.compression_min_bytes => {
+                        let encoded = encoding.encode(body.as_bytes())?;
+                        compressed = Some((encoding, encoded.len()));
+                        builder
+                            .header("Content-Encoding", encoding.as_str())
+                            .body(encoded)
+                    }
+                    _ => builder.body(body.to_string()),
+                };
+            }
+            let result: Result<ApiResponse, Box<dyn Error>> = match builder.send() {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    let body = response.text()?;
+                    if (200..300).contains(&status) {
+                        Ok(ApiResponse { status, body })
+                    } else {
+                        Err(HttpStatusError { status, body }.into())
+                    }
+                }
+                Err(e) => Err(e.into()),
+            };
+
+            if let (Some((encoding, compressed_bytes)), Some(body)) = (compressed, body) {
+                match result
+                    .as_ref()
+                    .err()
+                    .and_then(|e| e.downcast_ref::<HttpStatusError>())
+                {
+                    Some(error) if error.status == 415 => {
+                        eprintln!(
+                            "The server does not accept {} request bodies, sending them uncompressed",
+                            encoding.as_str()
+                        );
+                        self.compression.set(None);
+                        continue;
+                    }
+                    _ => {
+                        let mut stats = self.compression_stats.get();
+                        stats.requests += 1;
+                        stats.original_bytes += body.len();
+                        stats.compressed_bytes += compressed_bytes;
+                        self.compression_stats.set(stats);
+                    }
+                }
+            }
+
+            let retryable = match &result {
+                Ok(_) => false,
+                Err(e) => match e.downcast_ref::<HttpStatusError>() {
+                    Some(error) => self.retry.retry_statuses.contains(&error.status),
+                    None => e
+                        .downcast_ref::<reqwest::Error>()
+                        .is_some_and(|error| error.is_timeout() || error.is_connect()),
+                },
+            };
+            if !retryable || attempt >= self.retry.max_attempts {
+                if let Some(circuit_breaker) = &self.circuit_breaker {
+                    circuit_breaker.record(result.as_ref().is_err_and(|e| {
+                        e.downcast_ref::<HttpStatusError>()
+                            .is_none_or(|error| error.status >= 500)
+                    }));
+                }
+                return result;
+            }
+
+            let wait = Duration::from_millis(
+                self.retry
+                    .backoff_ms
+                    .saturating_mul(1 << (attempt - 1).min(16)),
+            );
+            if let Err(e) = &result {
+                eprintln!(
+                    "Request failed ({}), retrying in {} ms (attempt {} of {})",
+                    e,
+                    wait.as_millis(),
+                    attempt + 1,
+                    self.retry.max_attempts
+                );
+            }
+            std::thread::sleep(wait);
+            self.retries.set(self.retries.get() + 1);
+            attempt += 1;
+        }
+    }
+}
+
+/// Builds the proxy of the client.
+fn build_proxy(settings: &ProxySettings) -> Result<Proxy, Box<dyn Error>> {
+    let mut proxy = Proxy::all(settings.url.as_str())
+        .map_err(|e| format!("Invalid proxy URL {}: {}", settings.url, e))?;
+    if let Some(username) = &settings.username {
+        let password = match &settings.password_variable {
+            Some(variable) => std::env::var(variable).map_err(|_| {
+                format!(
+                    "The environment variable {} with the proxy password is not set",
+                    variable
+                )
+            })?,
+            None => String::new(),
+        };
+        proxy = proxy.basic_auth(username, &password);
+    }
+    if let Some(no_proxy) = &settings.no_proxy {
+        proxy = proxy.no_proxy(reqwest::NoProxy::from_string(no_proxy));
+    }
+    Ok(proxy)
+}
+
+/// Reads a PEM file.
+fn read_pem(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    std::fs::read(path)
+        .map_err(|e| format!("Cannot read the certificate file {}: {}", path, e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::throttle::DeferredError;
+    use native_tls::TlsAcceptor;
+    use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    /// Starts an HTTPS server on a local port that answers one connection with a JSON
+    /// body, using a certificate signed by the test CA.
+    fn serve_once() -> u16 {
+        let identity = native_tls::Identity::from_pkcs8(
+            &std::fs::read(fixture("server.pem")).unwrap(),
+            &std::fs::read(fixture("server.key")).unwrap(),
+        )
+        .unwrap();
+        let acceptor = TlsAcceptor::new(identity).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            // A client that does not trust the CA aborts the handshake
+            if let Ok(stream) = acceptor.accept(stream) {
+                answer(stream);
+            }
+        });
+        port
+    }
+
+    /// Starts an HTTPS server like `serve_once` that also requires a client certificate
+    /// signed by the test client CA.
+    fn serve_once_mutual_tls() -> u16 {
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor
+            .set_private_key_file(fixture("server.key"), SslFiletype::PEM)
+            .unwrap();
+        acceptor
+            .set_certificate_chain_file(fixture("server.pem"))
+            .unwrap();
+        acceptor.set_ca_file(fixture("client-ca.pem")).unwrap();
+        acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+        let acceptor = acceptor.build();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            if let Ok(stream) = acceptor.accept(stream) {
+                answer(stream);
+            }
+        });
+        port
+    }
+
+    /// Starts a plain HTTP server, or proxy, on a local port that answers one connection
+    /// and sends back the head of the request it received.
+    fn serve_once_plain() -> (u16, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            sender.send(answer(stream)).unwrap();
+        });
+        (port, receiver)
+    }
+
+    /// A local port nothing listens on.
+    fn closed_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    /// Reads a request and answers it with a JSON body.
+    ///
+    /// # Returns
+    ///
+    /// The request line and headers, with the header names in lowercase.
+    fn answer(mut stream: impl Read + Write) -> String {
+        let mut head = String::new();
+        let mut reader = BufReader::new(&mut stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+            head.push_str(&match line.split_once(':') {
+                Some((name, value)) if !head.is_empty() => {
+                    format!("{}:{}", name.to_lowercase(), value)
+                }
+                _ => line.clone(),
+            });
+            line.clear();
+        }
+        let body = r#"{"id":"srv-1"}"#;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        head
+    }
+
+    fn client_with_tls(tls: TlsSettings) -> Result<ApiClient, Box<dyn Error>> {
+        ApiClient::new(ClientSettings {
+            tls,
+            ..ClientSettings::default()
+        })
+    }
+
+    fn client_with_proxy(proxy: ProxySettings) -> Result<ApiClient, Box<dyn Error>> {
+        ApiClient::new(ClientSettings {
+            proxy: Some(proxy),
+            ..ClientSettings::default()
+        })
+    }
+
+    #[test]
+    fn trusts_the_root_given_in_ca_certificates() {
+        let port = serve_once();
+        let client = ApiClient::new(ClientSettings {
+            tls: TlsSettings {
+                ca_certificates: vec![fixture("ca.pem")],
+                ..TlsSettings::default()
+            },
+            ..ClientSettings::default()
+        })
+        .unwrap();
+        let response = client
+            .get(&format!("https://localhost:{}/activities", port))
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, r#"{"id":"srv-1"}"#);
+    }
+
+    #[test]
+    fn rejects_the_server_without_its_root() {
+        let port = serve_once();
+        let client = ApiClient::new(ClientSettings::default()).unwrap();
+        assert!(client
+            .get(&format!("https://localhost:{}/activities", port))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_a_file_without_certificates() {
+        let error = ApiClient::new(ClientSettings {
+            tls: TlsSettings {
+                ca_certificates: vec![fixture("server.key")],
+                ..TlsSettings::default()
+            },
+            ..ClientSettings::default()
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("server.key"));
+    }
+
+    #[test]
+    fn sends_the_client_certificate_to_servers_requiring_it() {
+        let port = serve_once_mutual_tls();
+        let client = client_with_tls(TlsSettings {
+            ca_certificates: vec![fixture("ca.pem")],
+            client_certificate: Some(fixture("client.pem")),
+            client_key: Some(fixture("client.key")),
+        })
+        .unwrap();
+        let response = client
+            .get(&format!("https://localhost:{}/activities", port))
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        let port = serve_once_mutual_tls();
+        let client = client_with_tls(TlsSettings {
+            ca_certificates: vec![fixture("ca.pem")],
+            ..TlsSettings::default()
+        })
+        .unwrap();
+        assert!(client
+            .get(&format!("https://localhost:{}/activities", port))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_client_certificate_settings() {
+        let tls = |certificate: Option<&str>, key: Option<&str>| TlsSettings {
+            client_certificate: certificate.map(fixture),
+            client_key: key.map(fixture),
+            ..TlsSettings::default()
+        };
+
+        let error = client_with_tls(tls(Some("client.pem"), Some("missing.key"))).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Cannot read the certificate file")
+                && error.to_string().contains("missing.key"),
+            "{}",
+            error
+        );
+
+        // A key in the traditional RSA format instead of PKCS#8
+        let error = client_with_tls(tls(Some("client.pem"), Some("client-rsa.key"))).unwrap_err();
+        assert!(
+            error.to_string().starts_with("Invalid client certificate"),
+            "{}",
+            error
+        );
+
+        let error = client_with_tls(tls(Some("client.pem"), None)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The client certificate and its key must be given together"
+        );
+    }
+
+    #[test]
+    fn sends_the_requests_through_the_proxy_with_its_credentials() {
+        let (port, requests) = serve_once_plain();
+        std::env::set_var("EXCEL_READER_TEST_PROXY_PASSWORD", "secreto");
+        let client = client_with_proxy(ProxySettings {
+            url: format!("http://127.0.0.1:{}", port),
+            no_proxy: Some("localhost,.club.local".to_string()),
+            username: Some("club".to_string()),
+            password_variable: Some("EXCEL_READER_TEST_PROXY_PASSWORD".to_string()),
+        })
+        .unwrap();
+        let response = client.get("http://api.example.test/activities").unwrap();
+        assert_eq!(response.status, 200);
+
+        let head = requests.recv().unwrap();
+        assert!(
+            head.starts_with("GET http://api.example.test/activities HTTP/1.1"),
+            "{}",
+            head
+        );
+        assert!(
+            head.contains("proxy-authorization: Basic Y2x1YjpzZWNyZXRv"),
+            "{}",
+            head
+        );
+    }
+
+    #[test]
+    fn skips_the_proxy_for_the_no_proxy_hosts() {
+        let proxy = |no_proxy: Option<&str>| ProxySettings {
+            url: format!("http://127.0.0.1:{}", closed_port()),
+            no_proxy: no_proxy.map(str::to_string),
+            username: None,
+            password_variable: None,
+        };
+
+        let (port, requests) = serve_once_plain();
+        let client = client_with_proxy(proxy(Some("localhost,127.0.0.1"))).unwrap();
+        let response = client
+            .get(&format!("http://127.0.0.1:{}/activities", port))
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /activities HTTP/1.1"));
+
+        // Other hosts go through the proxy, which is not listening
+        let (port, _) = serve_once_plain();
+        let client = client_with_proxy(proxy(Some("localhost"))).unwrap();
+        assert!(client
+            .get(&format!("http://127.0.0.1:{}/activities", port))
+            .is_err());
+    }
+
+    #[test]
+    fn fails_when_the_proxy_password_variable_is_unset() {
+        let error = client_with_proxy(ProxySettings {
+            url: "http://127.0.0.1:3128".to_string(),
+            no_proxy: None,
+            username: Some("club".to_string()),
+            password_variable: Some("EXCEL_READER_TEST_UNSET_PASSWORD".to_string()),
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The environment variable EXCEL_READER_TEST_UNSET_PASSWORD with the proxy password is not set"
+        );
+    }
+
+    #[test]
+    fn defers_the_requests_once_the_circuit_breaker_stops() {
+        let client = ApiClient::new(ClientSettings {
+            circuit_breaker: Some(CircuitBreakerSettings {
+                failure_threshold: 1,
+                cooldown_secs: 0,
+                max_probes: 0,
+            }),
+            ..ClientSettings::default()
+        })
+        .unwrap();
+        // Nothing listens on the port, so the first request fails to connect
+        let endpoint = format!("http://127.0.0.1:{}/activities", closed_port());
+
+        let error = client.send(Method::POST, "{}", &endpoint).unwrap_err();
+        assert!(!error.is::<DeferredError>());
+        for _ in 0..2 {
+            let error = client.send(Method::POST, "{}", &endpoint).unwrap_err();
+            assert!(error.is::<DeferredError>());
+        }
     }
 }
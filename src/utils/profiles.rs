@@ -0,0 +1,477 @@
+use super::post_request::{
+    ClientSettings, ContentEncoding, ProxySettings, RetryPolicy, TlsSettings,
+    DEFAULT_COMPRESSION_MIN_BYTES,
+};
+use super::signing::SigningSettings;
+use super::throttle::{CircuitBreakerSettings, RateLimit};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+/// Default file with the environment profiles.
+pub const DEFAULT_PROFILES_PATH: &str = "profiles.json";
+
+/// Where the credentials of a profile are read from. The secret itself is never
+/// written in the profiles file.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum Credentials {
+    /// An environment variable holding the token.
+    Env {
+        variable: String,
+        #[serde(flatten)]
+        header: CredentialHeader,
+    },
+    /// A file holding the token, e.g. a mounted secret.
+    File {
+        path: String,
+        #[serde(flatten)]
+        header: CredentialHeader,
+    },
+}
+
+/// How a token is written in the request headers.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CredentialHeader {
+    /// Header name, "Authorization" when missing.
+    #[serde(default = "default_credential_header")]
+    pub header: String,
+
+    /// Prefix of the token, "Bearer" when missing; an empty scheme sends the token alone.
+    #[serde(default = "default_credential_scheme")]
+    pub scheme: String,
+}
+
+fn default_credential_header() -> String {
+    "Authorization".to_string()
+}
+
+fn default_credential_scheme() -> String {
+    "Bearer".to_string()
+}
+
+impl Credentials {
+    /// Reads the token and returns the header carrying it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<(String, String), Box<dyn Error>>` with the header name and value, or an
+    /// error if the variable or file cannot be read.
+    pub fn resolve(&self) -> Result<(String, String), Box<dyn Error>> {
+        let (token, header) = match self {
+            Credentials::Env { variable, header } => (
+                std::env::var(variable).map_err(|_| {
+                    format!(
+                        "The environment variable {} with the credentials is not set",
+                        variable
+                    )
+                })?,
+                header,
+            ),
+            Credentials::File { path, header } => (
+                std::fs::read_to_string(path)
+                    .map_err(|e| format!("Cannot read the credentials file {}: {}", path, e))?,
+                header,
+            ),
+        };
+        let token = token.trim();
+        let value = match header.scheme.trim() {
+            "" => token.to_string(),
+            scheme => format!("{} {}", scheme, token),
+        };
+        Ok((header.header.clone(), value))
+    }
+}
+
+/// Settings of one environment (e.g., "local", "staging" or "production").
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Profile {
+    /// Value of the `{base}` placeholder of the URL templates.
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// URL template the activities are sent to, e.g. "{base}/activities".
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    /// Where the credentials are read from.
+    #[serde(default)]
+    pub credentials: Option<Credentials>,
+
+    /// Headers added to every request.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Total time allowed for each request, in seconds, from connecting until the whole
+    /// response body is read (not just the wait between reads).
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// Maximum duration of the connection to the server, in seconds.
+    #[serde(default)]
+    pub connect_timeout_secs: Option<u64>,
+
+    /// Retry policy of the failed requests.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+
+    /// Proxy of the requests; the proxy environment variables are used when missing.
+    #[serde(default)]
+    pub proxy: Option<ProxySettings>,
+
+    /// Extra root certificates and the client certificate of mutual TLS.
+    #[serde(default)]
+    pub tls: TlsSettings,
+
+    /// User agent of the requests.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    /// Signing of the requests required by the server, e.g. an HMAC of the body.
+    #[serde(default)]
+    pub signing: Option<SigningSettings>,
+
+    /// Content-Encoding of the request bodies ("gzip" or "deflate"), uncompressed when missing.
+    #[serde(default)]
+    pub compression: Option<ContentEncoding>,
+
+    /// Size from which the request bodies are compressed, in bytes (1024 when missing).
+    #[serde(default)]
+    pub compression_min_bytes: Option<usize>,
+
+    /// Maximum request rate, e.g. `{ "requests": 60, "per": "minute" }`.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+
+    /// Circuit breaker stopping the requests while the server is failing.
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerSettings>,
+
+    /// Directory where the files written by the program go, when given as relative paths.
+    #[serde(default)]
+    pub output_dir: Option<String>,
+
+    /// Whether sending to the profile needs an explicit confirmation.
+    #[serde(default)]
+    pub protected: bool,
+}
+
+impl Profile {
+    /// Builds the settings of the HTTP client, reading the credentials and signing secret.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<ClientSettings, Box<dyn Error>>` with the settings, or an error if the
+    /// credentials or the signing secret cannot be read.
+    pub fn client_settings(&self) -> Result<ClientSettings, Box<dyn Error>> {
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        if let Some(credentials) = &self.credentials {
+            headers.push(credentials.resolve()?);
+        }
+        Ok(ClientSettings {
+            headers,
+            timeout: self.timeout_secs.map(Duration::from_secs),
+            connect_timeout: self.connect_timeout_secs.map(Duration::from_secs),
+            retry: self.retry.clone(),
+            proxy: self.proxy.clone(),
+            tls: self.tls.clone(),
+            user_agent: self.user_agent.clone(),
+            signer: match &self.signing {
+                Some(signing) => Some(signing.signer()?),
+                None => None,
+            },
+            compression: self.compression,
+            compression_min_bytes: self
+                .compression_min_bytes
+                .unwrap_or(DEFAULT_COMPRESSION_MIN_BYTES),
+            rate_limit: self.rate_limit.clone(),
+            circuit_breaker: self.circuit_breaker.clone(),
+        })
+    }
+
+    /// Checks that a run may use the profile: sending to a protected profile needs an
+    /// explicit confirmation.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the profile.
+    /// * `sends` - Whether the run sends requests that change the server (not a dry run).
+    /// * `confirmed` - Whether `--confirm-protected` was given.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<(), Box<dyn Error>>` with an error if the confirmation is missing.
+    pub fn check_confirmed(
+        &self,
+        name: &str,
+        sends: bool,
+        confirmed: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.protected && sends && !confirmed {
+            return Err(format!(
+                "The profile '{}' is protected, add --confirm-protected to send to it",
+                name
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Places a relative output path inside the output directory of the profile.
+    pub fn output_path(&self, path: &str) -> String {
+        match &self.output_dir {
+            Some(output_dir) if Path::new(path).is_relative() => Path::new(output_dir)
+                .join(path)
+                .to_string_lossy()
+                .into_owned(),
+            _ => path.to_string(),
+        }
+    }
+}
+
+/// Profiles file, keyed by profile name.
+///
+/// ```json
+/// {
+///   "profiles": {
+///     "local": { "base_url": "http://localhost:8080/api", "endpoint": "{base}/activities" },
+///     "production": {
+///       "base_url": "https://api.example.com/v1",
+///       "endpoint": "{base}/activities",
+///       "credentials": { "source": "env", "variable": "PADEL_API_TOKEN" },
+///       "headers": { "X-Client": "excel_reader" },
+///       "timeout_secs": 30,
+///       "retry": { "max_attempts": 3, "backoff_ms": 1000 },
+///       "proxy": { "url": "http://proxy.club.local:3128", "no_proxy": "localhost" },
+///       "tls": { "ca_certificates": ["certs/staging-ca.pem"] },
+///       "output_dir": "runs/production",
+///       "protected": true
+///     }
+///   }
+/// }
+/// ```
+#[derive(Debug, Deserialize, Default)]
+pub struct ProfilesConfig {
+    /// The profiles, keyed by name.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Loads a profile from the profiles file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON profiles file.
+/// * `name` - The name of the profile.
+///
+/// # Returns
+///
+/// A `Result<Profile, Box<dyn Error>>` with the profile, or an error if the file has no
+/// profile with that name.
+pub fn load_profile(path: &str, name: &str) -> Result<Profile, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read the profiles file {}: {}", path, e))?;
+    let config: ProfilesConfig = serde_json::from_str(&data)?;
+    config.profiles.get(name).cloned().ok_or_else(|| {
+        format!(
+            "The profiles file {} has no profile '{}' (profiles: {})",
+            path,
+            name,
+            config
+                .profiles
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::cli::parse_args;
+    use serde_json::json;
+
+    /// Writes a file in a directory of its own under the temporary directory.
+    fn temp_file(test: &str, name: &str, contents: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("excel_reader-{}-{}", test, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn profiles_file(test: &str) -> String {
+        let profiles = json!({
+            "profiles": {
+                "local": { "base_url": "http://localhost:8080/api", "endpoint": "{base}/activities" },
+                "production": {
+                    "base_url": "https://api.example.com/v1",
+                    "endpoint": "{base}/activities",
+                    "headers": { "X-Client": "excel_reader" },
+                    "timeout_secs": 30,
+                    "retry": { "max_attempts": 3 },
+                    "output_dir": "runs/production",
+                    "protected": true
+                }
+            }
+        });
+        temp_file(test, "profiles.json", &profiles.to_string())
+    }
+
+    fn credentials(value: serde_json::Value) -> Credentials {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn args(args: &[&str]) -> crate::utils::cli::CliArgs {
+        parse_args(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    #[test]
+    fn loads_a_profile_by_name() {
+        let path = profiles_file("load");
+        let profile = load_profile(&path, "production").unwrap();
+        assert_eq!(
+            profile.base_url.as_deref(),
+            Some("https://api.example.com/v1")
+        );
+        assert!(profile.protected);
+        assert_eq!(profile.retry.max_attempts, 3);
+        // Unset fields keep their defaults
+        assert_eq!(profile.retry.backoff_ms, RetryPolicy::default().backoff_ms);
+
+        let settings = profile.client_settings().unwrap();
+        assert_eq!(
+            settings.headers,
+            [("X-Client".to_string(), "excel_reader".to_string())]
+        );
+        assert_eq!(settings.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(
+            settings.compression_min_bytes,
+            DEFAULT_COMPRESSION_MIN_BYTES
+        );
+    }
+
+    #[test]
+    fn names_the_known_profiles_when_one_is_missing() {
+        let path = profiles_file("missing");
+        let error = load_profile(&path, "staging").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The profiles file {} has no profile 'staging' (profiles: local, production)",
+                path
+            )
+        );
+        assert!(load_profile("missing-profiles.json", "local")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Cannot read the profiles file missing-profiles.json"));
+    }
+
+    #[test]
+    fn the_command_line_takes_precedence_over_the_profile() {
+        let profile = load_profile(&profiles_file("precedence"), "production").unwrap();
+
+        let mut from_profile = args(&["send", "--state", "state.json"]);
+        from_profile.apply_profile(&profile);
+        assert_eq!(from_profile.endpoint.as_deref(), Some("{base}/activities"));
+        assert_eq!(
+            from_profile.base_url.as_deref(),
+            Some("https://api.example.com/v1")
+        );
+        let state = std::path::Path::new("runs/production").join("state.json");
+        assert_eq!(from_profile.state.as_deref(), state.to_str());
+
+        let mut from_cli = args(&[
+            "send",
+            "--endpoint",
+            "http://localhost:9000/activities",
+            "--base-url",
+            "http://localhost:9000",
+            "--state",
+            "/tmp/state.json",
+        ]);
+        from_cli.apply_profile(&profile);
+        assert_eq!(
+            from_cli.endpoint.as_deref(),
+            Some("http://localhost:9000/activities")
+        );
+        assert_eq!(from_cli.base_url.as_deref(), Some("http://localhost:9000"));
+        assert_eq!(from_cli.state.as_deref(), Some("/tmp/state.json"));
+    }
+
+    #[test]
+    fn reads_the_credentials_from_an_environment_variable() {
+        std::env::set_var("EXCEL_READER_TEST_TOKEN", " abc123\n");
+        let bearer = credentials(json!({ "source": "env", "variable": "EXCEL_READER_TEST_TOKEN" }));
+        assert_eq!(
+            bearer.resolve().unwrap(),
+            ("Authorization".to_string(), "Bearer abc123".to_string())
+        );
+
+        let api_key = credentials(json!({
+            "source": "env",
+            "variable": "EXCEL_READER_TEST_TOKEN",
+            "header": "X-Api-Key",
+            "scheme": ""
+        }));
+        assert_eq!(
+            api_key.resolve().unwrap(),
+            ("X-Api-Key".to_string(), "abc123".to_string())
+        );
+
+        let unset = credentials(json!({ "source": "env", "variable": "EXCEL_READER_TEST_UNSET" }));
+        assert_eq!(
+            unset.resolve().unwrap_err().to_string(),
+            "The environment variable EXCEL_READER_TEST_UNSET with the credentials is not set"
+        );
+    }
+
+    #[test]
+    fn reads_the_credentials_from_a_file() {
+        let path = temp_file("credentials", "token", "secret-token\n");
+        let token = credentials(json!({ "source": "file", "path": path, "scheme": "Token" }));
+        assert_eq!(
+            token.resolve().unwrap(),
+            (
+                "Authorization".to_string(),
+                "Token secret-token".to_string()
+            )
+        );
+
+        let missing = credentials(json!({ "source": "file", "path": "missing-token" }));
+        assert!(missing
+            .resolve()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Cannot read the credentials file missing-token"));
+    }
+
+    #[test]
+    fn sending_to_a_protected_profile_needs_a_confirmation() {
+        let profile = load_profile(&profiles_file("protected"), "production").unwrap();
+        assert_eq!(
+            profile
+                .check_confirmed("production", true, false)
+                .unwrap_err()
+                .to_string(),
+            "The profile 'production' is protected, add --confirm-protected to send to it"
+        );
+        assert!(profile.check_confirmed("production", true, true).is_ok());
+        // Dry runs and commands that do not send need no confirmation
+        assert!(profile.check_confirmed("production", false, false).is_ok());
+
+        let local = load_profile(&profiles_file("unprotected"), "local").unwrap();
+        assert!(local.check_confirmed("local", true, false).is_ok());
+    }
+}
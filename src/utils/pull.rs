@@ -0,0 +1,70 @@
+use super::get_request::get_request;
+use super::golpes::GolpeCatalog;
+use super::post_request::ApiClient;
+use crate::models::{activity::Actividad, activity_sections::Seccion};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Downloads the activities published on a listing endpoint.
+///
+/// # Arguments
+///
+/// * `client` - The HTTP client.
+/// * `endpoint` - The URL of the listing endpoint, expected to return a JSON array of activities.
+///
+/// # Returns
+///
+/// A `Result<Vec<Actividad>, Box<dyn Error>>` with the deserialized activities. Activities
+/// that cannot be read are reported and skipped.
+pub fn fetch_actividades(
+    client: &ApiClient,
+    endpoint: &str,
+) -> Result<Vec<Actividad>, Box<dyn Error>> {
+    let body = get_request(client, endpoint)?;
+    let values: Vec<Value> = serde_json::from_str(&body)?;
+
+    // Skip the activities that cannot be read (e.g., with an unknown phase) instead of failing
+    let mut actividades = Vec::new();
+    for value in values {
+        let id = value
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        match serde_json::from_value(value) {
+            Ok(actividad) => actividades.push(actividad),
+            Err(e) => eprintln!("Skipping activity '{}': {}", id, e),
+        }
+    }
+    Ok(actividades)
+}
+
+/// Regroups a flat list of activities into one `Seccion` per golpe.
+///
+/// Activities are placed in the phase of the section matching their phase. A
+/// "MAIN_EXERCISE" phase without an index is read as the first main exercise block.
+///
+/// # Arguments
+///
+/// * `actividades` - The activities to regroup.
+/// * `catalog` - The golpe catalog, used to fill the golpe metadata of each section.
+///
+/// # Returns
+///
+/// A `BTreeMap<i32, Seccion>` keyed by golpe, in ascending order.
+pub fn group_by_golpe(
+    actividades: Vec<Actividad>,
+    catalog: &GolpeCatalog,
+) -> BTreeMap<i32, Seccion> {
+    let mut secciones: BTreeMap<i32, Seccion> = BTreeMap::new();
+
+    for actividad in actividades {
+        let seccion = secciones
+            .entry(actividad.golpe)
+            .or_insert_with(|| Seccion::new(catalog.find(actividad.golpe).cloned(), []));
+        seccion.fase_mut(actividad.phase).push(actividad);
+    }
+
+    secciones
+}
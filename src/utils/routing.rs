@@ -0,0 +1,456 @@
+use crate::models::{activity::Actividad, phase::Phase};
+use reqwest::Method;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use unicode_normalization::UnicodeNormalization;
+
+/// Placeholder values of a URL template, keyed by name.
+pub type UrlVariables = BTreeMap<&'static str, String>;
+
+/// Where and how a request is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destination {
+    /// The HTTP method.
+    pub method: Method,
+
+    /// The URL, with the placeholders already replaced.
+    pub url: String,
+}
+
+/// Endpoint of the activities of some phases and/or sheets.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Route {
+    /// Phase of the activities, e.g. "WARM_UP" or "MAIN_EXERCISE_2"; a phase kind such as
+    /// "MAIN_EXERCISE" matches every block of the kind. Every phase matches when missing.
+    #[serde(default)]
+    pub phase: Option<String>,
+
+    /// Name of the sheet of the activities. Every sheet matches when missing. Accented
+    /// letters match whether they are written as one character or with a combining mark.
+    #[serde(default)]
+    pub sheet: Option<String>,
+
+    /// URL template of the endpoint, e.g. "{base}/strokes/{golpe}/activities/{phase}".
+    pub url: String,
+
+    /// HTTP method of the requests, "POST" when missing.
+    #[serde(default)]
+    pub method: Option<String>,
+}
+
+impl Route {
+    fn matches(&self, sheet: &str, phase: Option<Phase>) -> bool {
+        let sheet_matches = self
+            .sheet
+            .as_deref()
+            .is_none_or(|name| name.trim().nfc().eq(sheet.trim().nfc()));
+        let phase_matches = match (&self.phase, phase) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(expected), Some(phase)) => {
+                let expected = expected.trim().to_uppercase();
+                match expected.as_str() {
+                    "MAIN_EXERCISE" => phase.kind() == expected,
+                    _ => expected.parse::<Phase>() == Ok(phase),
+                }
+            }
+        };
+        sheet_matches && phase_matches
+    }
+}
+
+/// Routing table mapping phases and sheets to endpoints. The first matching route is used.
+///
+/// ```json
+/// {
+///   "base": "https://api.example.com/v1",
+///   "routes": [
+///     { "phase": "WARM_UP", "url": "{base}/warm-ups" },
+///     { "sheet": "2. REVÉS PLANO", "url": "{base}/backhand/activities", "method": "PUT" },
+///     { "url": "{base}/strokes/{golpe}/activities/{phase}" }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RoutingTable {
+    /// Value of the `{base}` placeholder.
+    #[serde(default)]
+    pub base: Option<String>,
+
+    /// Routes, in priority order.
+    #[serde(default)]
+    pub routes: Vec<Route>,
+}
+
+impl RoutingTable {
+    /// Returns the first route matching a sheet and phase.
+    ///
+    /// Without a phase (e.g., for session documents), only the routes without a phase match.
+    pub fn find(&self, sheet: &str, phase: Option<Phase>) -> Option<&Route> {
+        self.routes.iter().find(|route| route.matches(sheet, phase))
+    }
+}
+
+/// Loads a routing table from a JSON file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON file.
+///
+/// # Returns
+///
+/// A `Result<RoutingTable, Box<dyn Error>>` with the routes, or an error if a route has
+/// an unknown phase or HTTP method.
+pub fn load_routing_table(path: &str) -> Result<RoutingTable, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let table: RoutingTable = serde_json::from_str(&data)?;
+
+    for route in &table.routes {
+        if let Some(phase) = &route.phase {
+            let phase = phase.trim().to_uppercase();
+            if !matches!(phase.as_str(), "WARM_UP" | "MAIN_EXERCISE" | "FINAL_PART") {
+                phase.parse::<Phase>()?;
+            }
+        }
+        if let Some(method) = &route.method {
+            parse_method(method)?;
+        }
+    }
+    Ok(table)
+}
+
+/// Resolves the destination of a request from the routing table, or from the default
+/// endpoint template when no route matches.
+///
+/// # Arguments
+///
+/// * `routes` - The routing table, if any.
+/// * `endpoint` - The default URL template, sent with POST.
+/// * `sheet` - The name of the sheet being sent.
+/// * `phase` - The phase of the activity, or `None` for requests covering the whole sheet.
+/// * `variables` - The values of the placeholders.
+///
+/// # Returns
+///
+/// A `Result<Destination, Box<dyn Error>>` with the method and URL, or an error if a
+/// placeholder has no value or if no route matches and the endpoint is empty.
+pub fn resolve_destination(
+    routes: Option<&RoutingTable>,
+    endpoint: &str,
+    sheet: &str,
+    phase: Option<Phase>,
+    variables: &UrlVariables,
+) -> Result<Destination, Box<dyn Error>> {
+    let (method, template) = match routes.and_then(|routes| routes.find(sheet, phase)) {
+        Some(route) => (
+            match &route.method {
+                Some(method) => parse_method(method)?,
+                None => Method::POST,
+            },
+            route.url.as_str(),
+        ),
+        None if endpoint.trim().is_empty() => return Err(no_endpoint(sheet, phase).into()),
+        None => (Method::POST, endpoint),
+    };
+    Ok(Destination {
+        method,
+        url: expand_url(template, variables)?,
+    })
+}
+
+/// Checks, before anything is read or sent, that the requests of a sheet have an
+/// endpoint: when the default endpoint is empty, a route must match every phase.
+///
+/// # Arguments
+///
+/// * `routes` - The routing table, if any.
+/// * `endpoint` - The default URL template.
+/// * `sheet` - The name of the sheet being sent.
+/// * `phases` - The phases of the requests, `None` for requests covering the whole sheet.
+///
+/// # Returns
+///
+/// A `Result<(), Box<dyn Error>>` with an error naming the first phase without endpoint.
+pub fn check_routes(
+    routes: Option<&RoutingTable>,
+    endpoint: &str,
+    sheet: &str,
+    phases: &[Option<Phase>],
+) -> Result<(), Box<dyn Error>> {
+    if !endpoint.trim().is_empty() {
+        return Ok(());
+    }
+    match phases.iter().find(|phase| {
+        routes
+            .and_then(|routes| routes.find(sheet, **phase))
+            .is_none()
+    }) {
+        Some(phase) => Err(no_endpoint(sheet, *phase).into()),
+        None => Ok(()),
+    }
+}
+
+/// Error of a request without a route nor a default endpoint.
+fn no_endpoint(sheet: &str, phase: Option<Phase>) -> String {
+    let requests = match phase {
+        Some(phase) => format!("the {} activities of the sheet '{}'", phase, sheet),
+        None => format!("the sheet '{}'", sheet),
+    };
+    format!("No route matches {} and no --endpoint was given", requests)
+}
+
+/// Placeholder values taken from an activity.
+///
+/// Available placeholders: `{base}`, `{sheet}`, `{id}`, `{golpe}`, `{phase}` (e.g.
+/// "MAIN_EXERCISE_2"), `{phase_kind}` (e.g. "MAIN_EXERCISE") and `{phase_index}`.
+pub fn activity_variables(actividad: &Actividad, sheet: &str, base: Option<&str>) -> UrlVariables {
+    let mut variables = sheet_variables(sheet, Some(actividad.golpe), base);
+    variables.insert("id", actividad.id.clone());
+    variables.insert("phase", actividad.phase.to_string());
+    variables.insert("phase_kind", actividad.phase.kind().to_string());
+    variables.insert("phase_index", actividad.phase.index().to_string());
+    variables
+}
+
+/// Placeholder values of the requests covering a whole sheet: `{base}`, `{sheet}` and,
+/// when the golpe of the sheet is known, `{golpe}`.
+pub fn sheet_variables(sheet: &str, golpe: Option<i32>, base: Option<&str>) -> UrlVariables {
+    let mut variables = UrlVariables::new();
+    variables.insert("sheet", sheet.to_string());
+    if let Some(golpe) = golpe {
+        variables.insert("golpe", golpe.to_string());
+    }
+    if let Some(base) = base {
+        variables.insert("base", base.trim_end_matches('/').to_string());
+    }
+    variables
+}
+
+/// Replaces the `{name}` placeholders of a URL template.
+///
+/// Values are percent-encoded as path segments, except `{base}`, which is inserted as is.
+///
+/// # Arguments
+///
+/// * `template` - The URL template.
+/// * `variables` - The values of the placeholders.
+///
+/// # Returns
+///
+/// A `Result<String, Box<dyn Error>>` with the URL, or an error if a placeholder has no value.
+pub fn expand_url(template: &str, variables: &UrlVariables) -> Result<String, Box<dyn Error>> {
+    let mut url = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| format!("Unclosed placeholder in the URL template {}", template))?;
+        let name = &rest[start + 1..end];
+        let value = variables.get(name).ok_or_else(|| {
+            format!(
+                "The URL template {} has an unknown placeholder {{{}}} (available: {})",
+                template,
+                name,
+                variables.keys().copied().collect::<Vec<_>>().join(", ")
+            )
+        })?;
+        url.push_str(&rest[..start]);
+        match name {
+            "base" => url.push_str(value),
+            _ => url.push_str(&encode_segment(value)),
+        }
+        rest = &rest[end + 1..];
+    }
+    url.push_str(rest);
+    Ok(url)
+}
+
+/// Parses an HTTP method name.
+fn parse_method(method: &str) -> Result<Method, Box<dyn Error>> {
+    Method::from_bytes(method.trim().to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid HTTP method: {}", method).into())
+}
+
+/// Percent-encodes a value for a URL path segment.
+pub(crate) fn encode_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            other => format!("%{:02X}", other),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(routes: serde_json::Value) -> RoutingTable {
+        serde_json::from_value(serde_json::json!({ "routes": routes })).unwrap()
+    }
+
+    fn variables() -> UrlVariables {
+        let mut variables = sheet_variables("1. DERECHA", Some(3), Some("https://api.test/v1/"));
+        variables.insert("phase", "MAIN_EXERCISE_2".to_string());
+        variables
+    }
+
+    fn destination(
+        routes: &RoutingTable,
+        sheet: &str,
+        phase: Option<Phase>,
+    ) -> Result<Destination, Box<dyn Error>> {
+        resolve_destination(Some(routes), "{base}/default", sheet, phase, &variables())
+    }
+
+    const MAIN_2: Phase = Phase::MainExercise { index: 2 };
+
+    #[test]
+    fn expands_the_placeholders() {
+        assert_eq!(
+            expand_url("{base}/strokes/{golpe}/activities/{phase}", &variables()).unwrap(),
+            "https://api.test/v1/strokes/3/activities/MAIN_EXERCISE_2"
+        );
+        assert_eq!(
+            expand_url("https://api.test/ping", &variables()).unwrap(),
+            "https://api.test/ping"
+        );
+    }
+
+    #[test]
+    fn percent_encodes_the_values_but_not_the_base() {
+        let mut variables = sheet_variables("2. REVÉS PLANO", None, Some("https://api.test/a b"));
+        variables.insert("golpe", "3/4".to_string());
+        assert_eq!(
+            expand_url("{base}/sheets/{sheet}/{golpe}", &variables).unwrap(),
+            "https://api.test/a b/sheets/2.%20REV%C3%89S%20PLANO/3%2F4"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_unclosed_placeholders() {
+        let error = expand_url("{base}/{stroke}", &variables()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The URL template {base}/{stroke} has an unknown placeholder {stroke} \
+             (available: base, golpe, phase, sheet)"
+        );
+        // {golpe} is only known when the golpe of the sheet is
+        let error = expand_url("{golpe}", &sheet_variables("NOTAS", None, None)).unwrap_err();
+        assert!(error.to_string().contains("unknown placeholder {golpe}"));
+
+        let error = expand_url("{base}/{golpe", &variables()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unclosed placeholder in the URL template {base}/{golpe"
+        );
+    }
+
+    #[test]
+    fn encodes_path_segments() {
+        assert_eq!(encode_segment("D-C1_a.b~"), "D-C1_a.b~");
+        assert_eq!(encode_segment("a b/c?d#e%"), "a%20b%2Fc%3Fd%23e%25");
+        assert_eq!(encode_segment("REVÉS"), "REV%C3%89S");
+    }
+
+    #[test]
+    fn matches_phases_and_phase_kinds() {
+        let route = |phase: &str| Route {
+            phase: Some(phase.to_string()),
+            sheet: None,
+            url: String::new(),
+            method: None,
+        };
+        assert!(route("WARM_UP").matches("S", Some(Phase::WarmUp)));
+        assert!(!route("WARM_UP").matches("S", Some(Phase::FinalPart)));
+        assert!(route(" main_exercise_2 ").matches("S", Some(MAIN_2)));
+        assert!(!route("MAIN_EXERCISE_2").matches("S", Some(Phase::MainExercise { index: 1 })));
+        for index in 1..=4 {
+            assert!(route("MAIN_EXERCISE").matches("S", Some(Phase::MainExercise { index })));
+        }
+        assert!(!route("MAIN_EXERCISE").matches("S", Some(Phase::WarmUp)));
+        // Requests covering the whole sheet only match routes without a phase
+        assert!(!route("MAIN_EXERCISE").matches("S", None));
+    }
+
+    #[test]
+    fn matches_sheets_with_accents_in_any_normalization() {
+        let route = Route {
+            phase: None,
+            sheet: Some("2. REVÉS PLANO".to_string()),
+            url: String::new(),
+            method: None,
+        };
+        assert!(route.matches("2. REVÉS PLANO", None));
+        assert!(route.matches("2. REVE\u{301}S PLANO ", Some(Phase::WarmUp)));
+        assert!(!route.matches("2. REVES PLANO", None));
+        assert!(!route.matches("1. DERECHA", None));
+    }
+
+    #[test]
+    fn uses_the_first_matching_route() {
+        let routes = table(serde_json::json!([
+            { "phase": "WARM_UP", "url": "{base}/warm-ups" },
+            { "phase": "MAIN_EXERCISE", "url": "{base}/main/{phase}", "method": "put" },
+            { "phase": "MAIN_EXERCISE_2", "url": "{base}/never" },
+            { "sheet": "1. DERECHA", "url": "{base}/sheets/{sheet}" },
+        ]));
+        assert_eq!(
+            destination(&routes, "1. DERECHA", Some(MAIN_2)).unwrap(),
+            Destination {
+                method: Method::PUT,
+                url: "https://api.test/v1/main/MAIN_EXERCISE_2".to_string(),
+            }
+        );
+        assert_eq!(
+            destination(&routes, "1. DERECHA", Some(Phase::WarmUp))
+                .unwrap()
+                .url,
+            "https://api.test/v1/warm-ups"
+        );
+        let sheet = destination(&routes, "1. DERECHA", None).unwrap();
+        assert_eq!(sheet.method, Method::POST);
+        assert_eq!(sheet.url, "https://api.test/v1/sheets/1.%20DERECHA");
+        assert_eq!(
+            destination(&routes, "2. REVÉS", Some(Phase::FinalPart))
+                .unwrap()
+                .url,
+            "https://api.test/v1/default"
+        );
+    }
+
+    #[test]
+    fn needs_a_route_for_every_phase_without_endpoint() {
+        let routes = table(serde_json::json!([
+            { "phase": "WARM_UP", "url": "{base}/warm-ups" },
+            { "phase": "MAIN_EXERCISE", "url": "{base}/main" },
+        ]));
+        let error = resolve_destination(
+            Some(&routes),
+            "",
+            "1. DERECHA",
+            Some(Phase::FinalPart),
+            &variables(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No route matches the FINAL_PART activities of the sheet '1. DERECHA' and no --endpoint was given"
+        );
+
+        let phases = [Some(Phase::WarmUp), Some(MAIN_2)];
+        assert!(check_routes(Some(&routes), "", "1. DERECHA", &phases).is_ok());
+        assert!(check_routes(None, "{base}/default", "1. DERECHA", &[None]).is_ok());
+        let error = check_routes(Some(&routes), " ", "1. DERECHA", &[None]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No route matches the sheet '1. DERECHA' and no --endpoint was given"
+        );
+        assert!(check_routes(None, "", "1. DERECHA", &phases).is_err());
+    }
+}
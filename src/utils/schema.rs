@@ -0,0 +1,730 @@
+use super::parser::{
+    LEVEL_TERMS, MATERIAL_TERMS, MODEL_TERMS, PART_TO_PRACTICE_TERMS, SHOT_TERMS, TYPOLOGY_TERMS,
+};
+use super::payload::{PayloadOptions, PayloadVersion};
+use crate::models::phase::PhaseFormat;
+use regex::Regex;
+use serde_json::{json, Value};
+
+/// URI of the JSON Schema dialect of the generated schemas.
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Document described by a schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaDocument {
+    /// The payload of a single activity.
+    Activity,
+    /// The session document sent with `--send-mode session`.
+    Session,
+}
+
+impl std::str::FromStr for SchemaDocument {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "activity" => Ok(SchemaDocument::Activity),
+            "session" => Ok(SchemaDocument::Session),
+            other => Err(format!("Unknown schema document: {}", other)),
+        }
+    }
+}
+
+/// Builds the JSON Schema of a payload.
+///
+/// The schema describes the payload before the payload mapping is applied, in the
+/// version and phase format of `options`.
+///
+/// # Arguments
+///
+/// * `document` - The document to describe.
+/// * `options` - The payload settings.
+///
+/// # Returns
+///
+/// The JSON Schema as a `serde_json::Value`.
+pub fn payload_schema(document: SchemaDocument, options: &PayloadOptions) -> Value {
+    let version = options.version;
+    let mut schema = match document {
+        SchemaDocument::Activity => actividad_schema(options),
+        SchemaDocument::Session => session_schema(options),
+    };
+    let name = match document {
+        SchemaDocument::Activity => "actividad",
+        SchemaDocument::Session => "session",
+    };
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("$schema".to_string(), json!(SCHEMA_DIALECT));
+        object.insert("$id".to_string(), json!(format!("{}-{}", name, version)));
+    }
+    schema
+}
+
+/// Schema of the payload of a single activity.
+fn actividad_schema(options: &PayloadOptions) -> Value {
+    let (typology, level, model, shot, part_to_practice, equipment, duration) =
+        match options.version {
+            PayloadVersion::V1 => (
+                string_list(None),
+                string_list(None),
+                string_list(None),
+                string_list(None),
+                string_list(None),
+                string_list(None),
+                json!({ "type": "string" }),
+            ),
+            PayloadVersion::V2 => (
+                string_list(Some(english_terms(&TYPOLOGY_TERMS, false))),
+                string_list(Some(english_terms(&LEVEL_TERMS, false))),
+                string_list(Some(english_terms(&MODEL_TERMS, false))),
+                string_list(Some(english_terms(&SHOT_TERMS, true))),
+                string_list(Some(english_terms(&PART_TO_PRACTICE_TERMS, true))),
+                string_list(Some(english_terms(&MATERIAL_TERMS, true))),
+                json!({
+                    "type": "object",
+                    "required": ["minutes", "text"],
+                    "properties": {
+                        "minutes": { "type": ["integer", "null"], "minimum": 0 },
+                        "text": { "type": "string" }
+                    },
+                    "additionalProperties": false
+                }),
+            ),
+        };
+
+    json!({
+        "title": format!("Actividad ({})", options.version),
+        "type": "object",
+        "required": [
+            "id", "golpe", "phase", "num_jugadores", "typology", "level", "model", "shot",
+            "part_to_practice", "equipment", "duration", "content"
+        ],
+        "properties": {
+            "id": { "type": "string", "minLength": 1 },
+            "golpe": { "type": "integer", "minimum": 0 },
+            "phase": phase_schema(options.phase_format),
+            "num_jugadores": { "type": "array", "items": { "type": "integer", "minimum": 0 } },
+            "typology": typology,
+            "level": level,
+            "model": model,
+            "shot": shot,
+            "part_to_practice": part_to_practice,
+            "equipment": equipment,
+            "duration": duration,
+            "content": {
+                "type": "object",
+                "propertyNames": { "pattern": "^[A-Z]{2}$" },
+                "additionalProperties": {
+                    "type": "object",
+                    "required": ["title", "goal", "script"],
+                    "properties": {
+                        "title": { "type": "string" },
+                        "goal": { "type": "string" },
+                        "script": { "type": "string" },
+                        "machine_filled": { "type": "boolean" }
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "provenance": {
+                "type": "object",
+                "required": ["file", "sheet", "row", "columns"],
+                "properties": {
+                    "file": { "type": "string" },
+                    "sheet": { "type": "string" },
+                    "row": { "type": "integer", "minimum": 1 },
+                    "columns": { "type": "string" }
+                },
+                "additionalProperties": false
+            }
+        },
+        "additionalProperties": false
+    })
+}
+
+/// Schema of the session document, referencing the activity schema.
+fn session_schema(options: &PayloadOptions) -> Value {
+    json!({
+        "title": format!("Session ({})", options.version),
+        "type": "object",
+        "required": ["golpe", "phases", "totals"],
+        "properties": {
+            "golpe": {
+                "type": ["object", "null"],
+                "required": ["id", "name_es", "name_en", "api_code"],
+                "properties": {
+                    "id": { "type": "integer" },
+                    "name_es": { "type": "string" },
+                    "name_en": { "type": "string" },
+                    "api_code": { "type": "string" }
+                },
+                "additionalProperties": false
+            },
+            "phases": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["phase", "duration_minutes", "activities"],
+                    "properties": {
+                        "phase": phase_schema(options.phase_format),
+                        "duration_minutes": { "type": "integer", "minimum": 0 },
+                        "activities": { "type": "array", "items": { "$ref": "#/$defs/actividad" } }
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "totals": {
+                "type": "object",
+                "required": ["activities", "duration_minutes", "unknown_durations", "equipment"],
+                "properties": {
+                    "activities": { "type": "integer", "minimum": 0 },
+                    "duration_minutes": { "type": "integer", "minimum": 0 },
+                    "unknown_durations": { "type": "integer", "minimum": 0 },
+                    "equipment": { "type": "array", "items": { "type": "string" } }
+                },
+                "additionalProperties": false
+            }
+        },
+        "additionalProperties": false,
+        "$defs": { "actividad": actividad_schema(options) }
+    })
+}
+
+/// Schema of the phase in the given wire format.
+fn phase_schema(format: PhaseFormat) -> Value {
+    match format {
+        PhaseFormat::Legacy => json!({ "enum": ["WARM_UP", "MAIN_EXERCISE", "FINAL_PART"] }),
+        PhaseFormat::Indexed => json!({
+            "type": "string",
+            "pattern": "^(WARM_UP|FINAL_PART|MAIN_EXERCISE_[1-9][0-9]*)$"
+        }),
+        PhaseFormat::Object => json!({
+            "type": "object",
+            "required": ["type", "index"],
+            "properties": {
+                "type": { "enum": ["WARM_UP", "MAIN_EXERCISE", "FINAL_PART"] },
+                "index": { "type": "integer", "minimum": 1 }
+            },
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// Schema of a list of strings, optionally restricted to some values.
+fn string_list(values: Option<Vec<String>>) -> Value {
+    match values {
+        Some(values) => json!({ "type": "array", "items": { "enum": values } }),
+        None => json!({ "type": "array", "items": { "type": "string" } }),
+    }
+}
+
+/// English values of a translation table, as written in the payload.
+fn english_terms(terms: &[(&str, &str)], uppercase: bool) -> Vec<String> {
+    terms
+        .iter()
+        .map(|(_, english)| match uppercase {
+            true => english.to_uppercase(),
+            false => english.to_string(),
+        })
+        .collect()
+}
+
+/// Checks a JSON value against a schema.
+///
+/// Only a subset of JSON Schema is supported: local `$ref`, `type`, `nullable`, `enum`,
+/// `const`, `allOf`, `anyOf`, `oneOf`, `required`, `properties`, `additionalProperties`,
+/// `propertyNames`, `items`, `minItems`, `maxItems`, `minimum`, `maximum`, `minLength`,
+/// `maxLength` and `pattern`. Other keywords are ignored.
+///
+/// # Arguments
+///
+/// * `schema` - The schema, with its `$defs` at the root.
+/// * `value` - The value to check.
+///
+/// # Returns
+///
+/// A `Vec<String>` with one message per violation, prefixed by the JSON pointer of the value.
+pub fn validate_json(schema: &Value, value: &Value) -> Vec<String> {
+    validate_json_in(schema, schema, value)
+}
+
+/// Checks a JSON value against a schema embedded in a larger document, such as an
+/// OpenAPI specification, against which its `$ref` are resolved.
+///
+/// # Arguments
+///
+/// * `document` - The document containing the schema.
+/// * `schema` - The schema.
+/// * `value` - The value to check.
+///
+/// # Returns
+///
+/// A `Vec<String>` with one message per violation, as in `validate_json`.
+pub fn validate_json_in(document: &Value, schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(document, schema, value, "", &mut errors);
+    errors
+}
+
+fn check(root: &Value, schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let location = if path.is_empty() { "/" } else { path };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(target) => check(root, target, value, path, errors),
+            None => errors.push(format!("{}: unresolved reference {}", location, reference)),
+        }
+        return;
+    }
+
+    // OpenAPI 3.0 marks nullable values with a keyword instead of a "null" type
+    if value.is_null() && schema.get("nullable") == Some(&Value::Bool(true)) {
+        return;
+    }
+
+    if let Some(subschemas) = schema.get("allOf").and_then(Value::as_array) {
+        for subschema in subschemas {
+            check(root, subschema, value, path, errors);
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(subschemas) = schema.get(keyword).and_then(Value::as_array) {
+            let matches = subschemas
+                .iter()
+                .filter(|subschema| {
+                    let mut subschema_errors = Vec::new();
+                    check(root, subschema, value, path, &mut subschema_errors);
+                    subschema_errors.is_empty()
+                })
+                .count();
+            if matches == 0 || (keyword == "oneOf" && matches > 1) {
+                errors.push(format!(
+                    "{}: matches {} of the {} schemas of {}",
+                    location,
+                    matches,
+                    subschemas.len(),
+                    keyword
+                ));
+            }
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.iter().any(|name| has_type(value, name)) {
+            errors.push(format!(
+                "{}: expected {}, found {}",
+                location,
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!("{}: {} is not an allowed value", location, value));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            errors.push(format!(
+                "{}: expected {}, found {}",
+                location, constant, value
+            ));
+        }
+    }
+
+    if let (Some(minimum), Some(number)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if number < minimum {
+            errors.push(format!("{}: {} is less than {}", location, number, minimum));
+        }
+    }
+    if let (Some(maximum), Some(number)) = (
+        schema.get("maximum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if number > maximum {
+            errors.push(format!(
+                "{}: {} is greater than {}",
+                location, number, maximum
+            ));
+        }
+    }
+
+    if let Value::String(text) = value {
+        if let Some(min_length) = schema.get("minLength").and_then(Value::as_u64) {
+            if (text.chars().count() as u64) < min_length {
+                errors.push(format!(
+                    "{}: shorter than {} characters",
+                    location, min_length
+                ));
+            }
+        }
+        if let Some(max_length) = schema.get("maxLength").and_then(Value::as_u64) {
+            if (text.chars().count() as u64) > max_length {
+                errors.push(format!(
+                    "{}: longer than {} characters",
+                    location, max_length
+                ));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            match Regex::new(pattern) {
+                Ok(regex) if !regex.is_match(text) => errors.push(format!(
+                    "{}: '{}' does not match {}",
+                    location, text, pattern
+                )),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("{}: invalid pattern {}: {}", location, pattern, e)),
+            }
+        }
+    }
+
+    if let Value::Array(values) = value {
+        if let Some(min_items) = schema.get("minItems").and_then(Value::as_u64) {
+            if (values.len() as u64) < min_items {
+                errors.push(format!("{}: fewer than {} items", location, min_items));
+            }
+        }
+        if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (values.len() as u64) > max_items {
+                errors.push(format!("{}: more than {} items", location, max_items));
+            }
+        }
+    }
+
+    if let (Some(items), Value::Array(values)) = (schema.get("items"), value) {
+        for (index, item) in values.iter().enumerate() {
+            check(root, items, item, &format!("{}/{}", path, index), errors);
+        }
+    }
+
+    if let Value::Object(object) = value {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!(
+                        "{}: missing required property '{}'",
+                        location, name
+                    ));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, item) in object {
+            let item_path = format!("{}/{}", path, name);
+            if let Some(names) = schema.get("propertyNames") {
+                check(
+                    root,
+                    names,
+                    &Value::String(name.clone()),
+                    &item_path,
+                    errors,
+                );
+            }
+            match (
+                properties.and_then(|properties| properties.get(name)),
+                schema.get("additionalProperties"),
+            ) {
+                (Some(property), _) => check(root, property, item, &item_path, errors),
+                (None, Some(Value::Bool(false))) => {
+                    errors.push(format!("{}: property '{}' is not allowed", location, name))
+                }
+                (None, Some(additional)) if additional.is_object() => {
+                    check(root, additional, item, &item_path, errors)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns `true` if a value has the given JSON Schema type.
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.is_i64() || value.is_u64(),
+        other => type_name(value) == other || (other == "number" && value.is_number()),
+    }
+}
+
+/// JSON Schema type of a value.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(version: PayloadVersion, phase_format: PhaseFormat) -> PayloadOptions {
+        PayloadOptions {
+            version,
+            phase_format,
+            ..PayloadOptions::default()
+        }
+    }
+
+    fn activity(version: PayloadVersion) -> Value {
+        let duration = match version {
+            PayloadVersion::V1 => json!("10m - 5m/pareja"),
+            PayloadVersion::V2 => json!({ "minutes": 10, "text": "10m - 5m/pareja" }),
+        };
+        json!({
+            "id": "D-C1",
+            "golpe": 1,
+            "phase": "WARM_UP",
+            "num_jugadores": [2, 3, 4],
+            "typology": ["IMPROVEMENT"],
+            "level": ["EASY", "MEDIUM"],
+            "model": ["TECHNIQUE"],
+            "shot": ["FOREHAND"],
+            "part_to_practice": ["DEPTH"],
+            "equipment": ["CONES"],
+            "duration": duration,
+            "content": {
+                "ES": { "title": "Calentar", "goal": "Activar", "script": "Pelotear suave" }
+            }
+        })
+    }
+
+    /// Violations of the V1 or V2 activity schema after changing the value at `pointer`.
+    fn errors_with(version: PayloadVersion, pointer: &str, value: Value) -> Vec<String> {
+        let schema = payload_schema(
+            SchemaDocument::Activity,
+            &options(version, PhaseFormat::Legacy),
+        );
+        let mut payload = activity(version);
+        *payload.pointer_mut(pointer).unwrap() = value;
+        validate_json(&schema, &payload)
+    }
+
+    #[test]
+    fn valid_activities_pass() {
+        for version in [PayloadVersion::V1, PayloadVersion::V2] {
+            let schema = payload_schema(
+                SchemaDocument::Activity,
+                &options(version, PhaseFormat::Legacy),
+            );
+            assert_eq!(
+                validate_json(&schema, &activity(version)),
+                Vec::<String>::new()
+            );
+        }
+    }
+
+    #[test]
+    fn valid_session_passes() {
+        let schema = payload_schema(
+            SchemaDocument::Session,
+            &options(PayloadVersion::V2, PhaseFormat::Indexed),
+        );
+        let session = json!({
+            "golpe": null,
+            "phases": [{
+                "phase": "MAIN_EXERCISE_2",
+                "duration_minutes": 10,
+                "activities": [activity(PayloadVersion::V2)]
+            }],
+            "totals": {
+                "activities": 1,
+                "duration_minutes": 10,
+                "unknown_durations": 0,
+                "equipment": ["CONES"]
+            }
+        });
+        assert_eq!(validate_json(&schema, &session), Vec::<String>::new());
+    }
+
+    #[test]
+    fn wrong_type_fails() {
+        assert_eq!(
+            errors_with(PayloadVersion::V1, "/golpe", json!("1")),
+            vec!["/golpe: expected integer, found string"]
+        );
+        assert_eq!(
+            errors_with(PayloadVersion::V2, "/duration", json!("10m")),
+            vec!["/duration: expected object, found string"]
+        );
+    }
+
+    #[test]
+    fn value_outside_enum_fails() {
+        assert_eq!(
+            errors_with(PayloadVersion::V2, "/level/0", json!("FACIL")),
+            vec![r#"/level/0: "FACIL" is not an allowed value"#]
+        );
+        assert_eq!(
+            errors_with(PayloadVersion::V1, "/phase", json!("MAIN_EXERCISE_1")),
+            vec![r#"/phase: "MAIN_EXERCISE_1" is not an allowed value"#]
+        );
+        // V1 accepts any text in the lists
+        assert!(errors_with(PayloadVersion::V1, "/level/0", json!("FACIL")).is_empty());
+    }
+
+    #[test]
+    fn missing_required_property_fails() {
+        let schema = payload_schema(
+            SchemaDocument::Activity,
+            &options(PayloadVersion::V1, PhaseFormat::Legacy),
+        );
+        let mut payload = activity(PayloadVersion::V1);
+        payload.as_object_mut().unwrap().remove("golpe");
+        assert_eq!(
+            validate_json(&schema, &payload),
+            vec!["/: missing required property 'golpe'"]
+        );
+        assert_eq!(
+            errors_with(PayloadVersion::V2, "/duration", json!({ "minutes": 10 })),
+            vec!["/duration: missing required property 'text'"]
+        );
+    }
+
+    #[test]
+    fn additional_property_fails() {
+        let schema = payload_schema(
+            SchemaDocument::Activity,
+            &options(PayloadVersion::V1, PhaseFormat::Legacy),
+        );
+        let mut payload = activity(PayloadVersion::V1);
+        payload["extra"] = json!(true);
+        assert_eq!(
+            validate_json(&schema, &payload),
+            vec!["/: property 'extra' is not allowed"]
+        );
+        payload = activity(PayloadVersion::V1);
+        payload["content"]["ES"]["notes"] = json!("no");
+        assert_eq!(
+            validate_json(&schema, &payload),
+            vec!["/content/ES: property 'notes' is not allowed"]
+        );
+    }
+
+    #[test]
+    fn invalid_item_fails() {
+        assert_eq!(
+            errors_with(PayloadVersion::V1, "/num_jugadores", json!([2, "3"])),
+            vec!["/num_jugadores/1: expected integer, found string"]
+        );
+    }
+
+    #[test]
+    fn value_below_minimum_fails() {
+        assert_eq!(
+            errors_with(PayloadVersion::V1, "/num_jugadores/0", json!(-1)),
+            vec!["/num_jugadores/0: -1 is less than 0"]
+        );
+        assert_eq!(
+            errors_with(PayloadVersion::V2, "/duration/minutes", json!(-5)),
+            vec!["/duration/minutes: -5 is less than 0"]
+        );
+    }
+
+    #[test]
+    fn text_not_matching_pattern_fails() {
+        let schema = payload_schema(
+            SchemaDocument::Activity,
+            &options(PayloadVersion::V2, PhaseFormat::Indexed),
+        );
+        let mut payload = activity(PayloadVersion::V2);
+        payload["phase"] = json!("MAIN_EXERCISE_0");
+        assert_eq!(
+            validate_json(&schema, &payload),
+            vec!["/phase: 'MAIN_EXERCISE_0' does not match ^(WARM_UP|FINAL_PART|MAIN_EXERCISE_[1-9][0-9]*)$"]
+        );
+    }
+
+    #[test]
+    fn invalid_property_name_fails() {
+        let schema = payload_schema(
+            SchemaDocument::Activity,
+            &options(PayloadVersion::V1, PhaseFormat::Legacy),
+        );
+        let mut payload = activity(PayloadVersion::V1);
+        payload["content"]["spanish"] = payload["content"]["ES"].clone();
+        assert_eq!(
+            validate_json(&schema, &payload),
+            vec!["/content/spanish: 'spanish' does not match ^[A-Z]{2}$"]
+        );
+    }
+
+    #[test]
+    fn empty_text_fails_min_length() {
+        assert_eq!(
+            errors_with(PayloadVersion::V1, "/id", json!("")),
+            vec!["/id: shorter than 1 characters"]
+        );
+    }
+
+    #[test]
+    fn object_phase_is_checked() {
+        let schema = payload_schema(
+            SchemaDocument::Activity,
+            &options(PayloadVersion::V1, PhaseFormat::Object),
+        );
+        let mut payload = activity(PayloadVersion::V1);
+        payload["phase"] = json!({ "type": "MAIN_EXERCISE", "index": 0 });
+        assert_eq!(
+            validate_json(&schema, &payload),
+            vec!["/phase/index: 0 is less than 1"]
+        );
+    }
+
+    #[test]
+    fn other_keywords_are_checked() {
+        let schema = json!({
+            "$defs": { "small": { "type": "integer", "maximum": 3 } },
+            "type": "object",
+            "properties": {
+                "ref": { "$ref": "#/$defs/small" },
+                "const": { "const": "v2" },
+                "one_of": { "oneOf": [{ "type": "integer" }, { "type": "number" }] },
+                "any_of": { "anyOf": [{ "type": "string" }, { "type": "boolean" }] },
+                "list": { "type": "array", "minItems": 1, "maxItems": 2 },
+                "nullable": { "type": "string", "nullable": true, "maxLength": 2 }
+            }
+        });
+        let valid = json!({
+            "ref": 3, "const": "v2", "one_of": 1.5, "any_of": true, "list": [1], "nullable": null
+        });
+        assert_eq!(validate_json(&schema, &valid), Vec::<String>::new());
+
+        let invalid = json!({
+            "ref": 4, "const": "v1", "one_of": 1, "any_of": 1, "list": [1, 2, 3], "nullable": "abc"
+        });
+        assert_eq!(
+            validate_json(&schema, &invalid),
+            vec![
+                "/any_of: matches 0 of the 2 schemas of anyOf",
+                r#"/const: expected "v2", found "v1""#,
+                "/list: more than 2 items",
+                "/nullable: longer than 2 characters",
+                "/one_of: matches 2 of the 2 schemas of oneOf",
+                "/ref: 4 is greater than 3",
+            ]
+        );
+        assert_eq!(
+            validate_json(&json!({ "$ref": "#/$defs/missing" }), &json!(1)),
+            vec!["/: unresolved reference #/$defs/missing"]
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use super::parser::duration_minutes;
+use crate::models::{
+    activity_sections::Seccion,
+    session::{SessionDocument, SessionPhase, SessionTotals},
+};
+use std::collections::BTreeSet;
+
+/// Builds the session document of a section, computing its totals.
+///
+/// # Arguments
+///
+/// * `seccion` - The section to describe.
+///
+/// # Returns
+///
+/// A `SessionDocument` with the golpe, the phases in order and the totals.
+pub fn session_document(seccion: &Seccion) -> SessionDocument {
+    let mut totals = SessionTotals::default();
+    let mut equipment = BTreeSet::new();
+    let mut phases = Vec::new();
+
+    for fase in &seccion.fases {
+        let mut phase_minutes = 0;
+        for actividad in &fase.actividades {
+            match duration_minutes(&actividad.duration) {
+                Some(minutes) => phase_minutes += minutes,
+                None => totals.unknown_durations += 1,
+            }
+            equipment.extend(actividad.equipment.iter().cloned());
+        }
+        totals.activities += fase.actividades.len();
+        totals.duration_minutes += phase_minutes;
+        phases.push(SessionPhase {
+            phase: fase.phase,
+            duration_minutes: phase_minutes,
+            activities: fase.actividades.clone(),
+        });
+    }
+    totals.equipment = equipment.into_iter().collect();
+
+    SessionDocument {
+        golpe: seccion.golpe.clone(),
+        phases,
+        totals,
+    }
+}
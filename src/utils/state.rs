@@ -0,0 +1,171 @@
+use super::routing::encode_segment;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Response fields stored for each activity.
+#[derive(Debug, Clone)]
+pub struct ResponseFields {
+    /// Dotted path of the server id in the response (e.g., "id" or "data.id").
+    pub server_id: String,
+
+    /// Dotted paths of the other fields to store (e.g., "createdAt" and "version").
+    pub fields: Vec<String>,
+}
+
+impl Default for ResponseFields {
+    fn default() -> Self {
+        ResponseFields {
+            server_id: "id".to_string(),
+            fields: vec!["createdAt".to_string(), "version".to_string()],
+        }
+    }
+}
+
+/// What the server returned for one activity.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SyncRecord {
+    /// Id of the activity on the server, used to update it later.
+    pub server_id: Option<String>,
+
+    /// The other stored response fields, keyed by path.
+    #[serde(default)]
+    pub fields: BTreeMap<String, Value>,
+}
+
+/// Contents of the state file, keyed by the id of the activity in the workbook.
+///
+/// ```json
+/// { "activities": { "D-C1": { "server_id": "srv-1", "fields": { "version": 1 } } } }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SyncState {
+    /// Stored response fields of each activity.
+    #[serde(default)]
+    pub activities: BTreeMap<String, SyncRecord>,
+}
+
+/// State file where the responses of the server are stored between runs.
+#[derive(Debug)]
+pub struct StateStore {
+    path: String,
+    fields: ResponseFields,
+    state: SyncState,
+}
+
+impl StateStore {
+    /// Opens a state file, starting with an empty state if it does not exist yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the JSON state file.
+    /// * `fields` - The response fields to store.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<StateStore, Box<dyn Error>>` with the store, or an error if the file
+    /// exists but cannot be read.
+    pub fn open(path: &str, fields: ResponseFields) -> Result<StateStore, Box<dyn Error>> {
+        let state = match std::fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SyncState::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(StateStore {
+            path: path.to_string(),
+            fields,
+            state,
+        })
+    }
+
+    /// Returns the server id of an activity, if it was already sent.
+    pub fn server_id(&self, id: &str) -> Option<&str> {
+        self.state.activities.get(id)?.server_id.as_deref()
+    }
+
+    /// Stores the configured fields of a JSON response for an activity.
+    ///
+    /// Fields missing from the response keep their stored value.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the activity in the workbook.
+    /// * `response` - The response of the server for the activity.
+    ///
+    /// # Returns
+    ///
+    /// The stored record of the activity.
+    pub fn record(&mut self, id: &str, response: &Value) -> &SyncRecord {
+        let record = self.state.activities.entry(id.to_string()).or_default();
+        if let Some(server_id) = value_at(response, &self.fields.server_id) {
+            record.server_id = Some(match server_id {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            });
+        }
+        for path in &self.fields.fields {
+            if let Some(value) = value_at(response, path) {
+                record.fields.insert(path.clone(), value.clone());
+            }
+        }
+        record
+    }
+
+    /// Parses a response body and stores its fields for an activity.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the activity in the workbook.
+    /// * `body` - The response body.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<&SyncRecord, Box<dyn Error>>` with the stored record, or an error if the
+    /// body is not JSON.
+    pub fn record_body(&mut self, id: &str, body: &str) -> Result<&SyncRecord, Box<dyn Error>> {
+        let response: Value = serde_json::from_str(body)
+            .map_err(|e| format!("The response is not JSON ({}): {}", e, body))?;
+        Ok(self.record(id, &response))
+    }
+
+    /// Writes the state file.
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.state)?)?;
+        Ok(())
+    }
+}
+
+/// URL of an activity already on the server, e.g. "https://api/activities/srv-1". The
+/// server id is percent-encoded as a single path segment.
+pub fn update_url(endpoint: &str, server_id: &str) -> String {
+    format!(
+        "{}/{}",
+        endpoint.trim_end_matches('/'),
+        encode_segment(server_id)
+    )
+}
+
+/// Returns the value at a dotted path of a JSON value.
+fn value_at<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, key| value.get(key))
+        .filter(|value| !value.is_null())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_url_encodes_the_server_id() {
+        assert_eq!(
+            update_url("https://api.example.com/activities/", "srv-1"),
+            "https://api.example.com/activities/srv-1"
+        );
+        assert_eq!(
+            update_url("https://api.example.com/activities", "a/b c?d#é"),
+            "https://api.example.com/activities/a%2Fb%20c%3Fd%23%C3%A9"
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use serde::Deserialize;
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Source of the current time and of the waits of the rate limiter and the circuit
+/// breaker, so that the tests can run without sleeping.
+pub trait Clock: fmt::Debug {
+    /// Current instant.
+    fn now(&self) -> Instant;
+
+    /// Waits for a duration.
+    fn sleep(&self, duration: Duration);
+}
+
+/// Clock of the system, which really waits.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Time unit of a rate limit.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RatePeriod {
+    Second,
+    Minute,
+}
+
+impl RatePeriod {
+    fn duration(&self) -> Duration {
+        match self {
+            RatePeriod::Second => Duration::from_secs(1),
+            RatePeriod::Minute => Duration::from_secs(60),
+        }
+    }
+}
+
+/// Maximum request rate, e.g. `{ "requests": 60, "per": "minute" }`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimit {
+    /// Number of requests allowed in each period.
+    pub requests: u32,
+
+    /// Period of the limit.
+    pub per: RatePeriod,
+
+    /// Requests that can be sent back to back before the rate applies, 1 when missing, so
+    /// that the requests are evenly spaced.
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+/// Token bucket spacing the requests to a rate limit.
+#[derive(Debug)]
+pub struct TokenBucket<C: Clock = SystemClock> {
+    capacity: f64,
+    tokens_per_second: f64,
+    tokens: Cell<f64>,
+    last_refill: Cell<Instant>,
+    clock: C,
+}
+
+impl TokenBucket {
+    /// Builds a full bucket.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<TokenBucket, Box<dyn Error>>` with the bucket, or an error if the limit
+    /// allows no requests.
+    pub fn new(limit: &RateLimit) -> Result<TokenBucket, Box<dyn Error>> {
+        TokenBucket::with_clock(limit, SystemClock)
+    }
+}
+
+impl<C: Clock> TokenBucket<C> {
+    /// Builds a full bucket that reads the time from `clock`.
+    pub fn with_clock(limit: &RateLimit, clock: C) -> Result<TokenBucket<C>, Box<dyn Error>> {
+        if limit.requests == 0 || limit.burst == 0 {
+            return Err("The rate limit must allow at least one request and a burst of one".into());
+        }
+        let capacity = f64::from(limit.burst);
+        Ok(TokenBucket {
+            capacity,
+            tokens_per_second: f64::from(limit.requests) / limit.per.duration().as_secs_f64(),
+            tokens: Cell::new(capacity),
+            last_refill: Cell::new(clock.now()),
+            clock,
+        })
+    }
+
+    /// Takes a token, waiting for one if the bucket is empty.
+    pub fn acquire(&self) {
+        self.refill();
+        let missing = 1.0 - self.tokens.get();
+        if missing > 0.0 {
+            self.clock
+                .sleep(Duration::from_secs_f64(missing / self.tokens_per_second));
+            self.refill();
+        }
+        self.tokens.set((self.tokens.get() - 1.0).max(0.0));
+    }
+
+    fn refill(&self) {
+        let now = self.clock.now();
+        let elapsed = now.duration_since(self.last_refill.get()).as_secs_f64();
+        self.tokens
+            .set((self.tokens.get() + elapsed * self.tokens_per_second).min(self.capacity));
+        self.last_refill.set(now);
+    }
+}
+
+/// When the circuit breaker stops sending, and how it checks whether the server is back.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CircuitBreakerSettings {
+    /// Consecutive failed requests that open the circuit.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// Wait before sending a probe request once the circuit is open, in seconds.
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+
+    /// Failed probes after which nothing else is sent in the run.
+    #[serde(default = "default_max_probes")]
+    pub max_probes: u32,
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_max_probes() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BreakerState {
+    /// Requests are sent; counts the consecutive failures.
+    Closed { failures: u32 },
+    /// Requests wait until `until`, then the next one is sent as a probe.
+    Open { until: Instant, failed_probes: u32 },
+    /// Every probe failed, nothing else is sent.
+    Stopped,
+}
+
+/// Circuit breaker that stops sending after consecutive failures.
+///
+/// A failure is a request that could not reach the server, timed out or got a 5xx status
+/// code, after its retries. Once open, the next request waits for the cooldown and is
+/// sent as a probe: if it succeeds the circuit closes, otherwise it opens again, until
+/// `max_probes` probes have failed.
+#[derive(Debug)]
+pub struct CircuitBreaker<C: Clock = SystemClock> {
+    settings: CircuitBreakerSettings,
+    state: Cell<BreakerState>,
+    clock: C,
+}
+
+impl CircuitBreaker {
+    /// Builds a closed circuit breaker.
+    pub fn new(settings: CircuitBreakerSettings) -> CircuitBreaker {
+        CircuitBreaker::with_clock(settings, SystemClock)
+    }
+}
+
+impl<C: Clock> CircuitBreaker<C> {
+    /// Builds a closed circuit breaker that reads the time from `clock`.
+    pub fn with_clock(settings: CircuitBreakerSettings, clock: C) -> CircuitBreaker<C> {
+        CircuitBreaker {
+            settings,
+            state: Cell::new(BreakerState::Closed { failures: 0 }),
+            clock,
+        }
+    }
+
+    /// Checks whether a request can be sent, waiting for the cooldown if the circuit is open.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<(), Box<dyn Error>>` that is a `DeferredError` once the run gave up.
+    pub fn before_request(&self) -> Result<(), Box<dyn Error>> {
+        match self.state.get() {
+            BreakerState::Closed { .. } => Ok(()),
+            BreakerState::Open { until, .. } => {
+                let now = self.clock.now();
+                if until > now {
+                    let wait = until - now;
+                    eprintln!(
+                        "Circuit breaker open, waiting {:.1} s before probing the server",
+                        wait.as_secs_f64()
+                    );
+                    self.clock.sleep(wait);
+                }
+                eprintln!("Probing the server");
+                Ok(())
+            }
+            BreakerState::Stopped => Err(DeferredError.into()),
+        }
+    }
+
+    /// Records the outcome of a request.
+    pub fn record(&self, failed: bool) {
+        let state = match (self.state.get(), failed) {
+            (BreakerState::Stopped, _) => BreakerState::Stopped,
+            (BreakerState::Open { .. }, false) => {
+                eprintln!("The server answered the probe, the circuit breaker closes");
+                BreakerState::Closed { failures: 0 }
+            }
+            (BreakerState::Closed { .. }, false) => BreakerState::Closed { failures: 0 },
+            (BreakerState::Closed { failures }, true) => {
+                if failures + 1 < self.settings.failure_threshold {
+                    BreakerState::Closed {
+                        failures: failures + 1,
+                    }
+                } else {
+                    eprintln!(
+                        "{} consecutive failed requests, the circuit breaker opens",
+                        failures + 1
+                    );
+                    self.open(0)
+                }
+            }
+            (BreakerState::Open { failed_probes, .. }, true) => {
+                eprintln!("The probe failed");
+                self.open(failed_probes + 1)
+            }
+        };
+        self.state.set(state);
+    }
+
+    fn open(&self, failed_probes: u32) -> BreakerState {
+        if failed_probes >= self.settings.max_probes {
+            eprintln!("The server is still failing, the remaining requests are deferred");
+            return BreakerState::Stopped;
+        }
+        BreakerState::Open {
+            until: self.clock.now() + Duration::from_secs(self.settings.cooldown_secs),
+            failed_probes,
+        }
+    }
+}
+
+/// Request not sent because the circuit breaker stopped sending.
+#[derive(Debug)]
+pub struct DeferredError;
+
+impl fmt::Display for DeferredError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not sent, the circuit breaker is open")
+    }
+}
+
+impl Error for DeferredError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    /// Clock that only moves when slept or advanced, and records the waits.
+    #[derive(Debug, Clone)]
+    struct FakeClock {
+        now: Rc<Cell<Instant>>,
+        slept: Rc<Cell<Duration>>,
+    }
+
+    impl FakeClock {
+        fn new() -> FakeClock {
+            FakeClock {
+                now: Rc::new(Cell::new(Instant::now())),
+                slept: Rc::new(Cell::new(Duration::ZERO)),
+            }
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+        }
+
+        /// Total time slept since the last call.
+        fn take_slept(&self) -> Duration {
+            self.slept.replace(Duration::ZERO)
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.advance(duration);
+            self.slept.set(self.slept.get() + duration);
+        }
+    }
+
+    fn rate(requests: u32, per: RatePeriod, burst: u32) -> RateLimit {
+        RateLimit {
+            requests,
+            per,
+            burst,
+        }
+    }
+
+    fn breaker(failure_threshold: u32, max_probes: u32) -> (CircuitBreaker<FakeClock>, FakeClock) {
+        let clock = FakeClock::new();
+        let settings = CircuitBreakerSettings {
+            failure_threshold,
+            cooldown_secs: 30,
+            max_probes,
+        };
+        (CircuitBreaker::with_clock(settings, clock.clone()), clock)
+    }
+
+    fn assert_secs(duration: Duration, secs: f64) {
+        assert!(
+            (duration.as_secs_f64() - secs).abs() < 1e-6,
+            "expected {} s, found {:?}",
+            secs,
+            duration
+        );
+    }
+
+    #[test]
+    fn bucket_spaces_requests_evenly() {
+        let clock = FakeClock::new();
+        let bucket =
+            TokenBucket::with_clock(&rate(2, RatePeriod::Second, 1), clock.clone()).unwrap();
+        bucket.acquire();
+        assert_eq!(clock.take_slept(), Duration::ZERO);
+        for _ in 0..3 {
+            bucket.acquire();
+            assert_secs(clock.take_slept(), 0.5);
+        }
+    }
+
+    #[test]
+    fn bucket_does_not_exceed_its_burst() {
+        let clock = FakeClock::new();
+        let bucket =
+            TokenBucket::with_clock(&rate(60, RatePeriod::Minute, 3), clock.clone()).unwrap();
+        for _ in 0..3 {
+            bucket.acquire();
+        }
+        assert_eq!(clock.take_slept(), Duration::ZERO);
+        bucket.acquire();
+        assert_secs(clock.take_slept(), 1.0);
+
+        // A long pause refills the bucket only up to the burst
+        clock.advance(Duration::from_secs(3600));
+        for _ in 0..3 {
+            bucket.acquire();
+        }
+        assert_eq!(clock.take_slept(), Duration::ZERO);
+        bucket.acquire();
+        assert_secs(clock.take_slept(), 1.0);
+    }
+
+    #[test]
+    fn bucket_counts_the_time_between_requests() {
+        let clock = FakeClock::new();
+        let bucket =
+            TokenBucket::with_clock(&rate(1, RatePeriod::Second, 1), clock.clone()).unwrap();
+        bucket.acquire();
+        clock.advance(Duration::from_millis(400));
+        bucket.acquire();
+        assert_secs(clock.take_slept(), 0.6);
+    }
+
+    #[test]
+    fn bucket_rejects_empty_limits() {
+        assert!(TokenBucket::new(&rate(0, RatePeriod::Second, 1)).is_err());
+        assert!(TokenBucket::new(&rate(1, RatePeriod::Second, 0)).is_err());
+    }
+
+    #[test]
+    fn breaker_opens_after_the_failure_threshold() {
+        let (breaker, clock) = breaker(3, 1);
+        breaker.record(true);
+        breaker.record(true);
+        // A success resets the count of consecutive failures
+        breaker.record(false);
+        breaker.record(true);
+        breaker.record(true);
+        assert!(breaker.before_request().is_ok());
+        assert_eq!(clock.take_slept(), Duration::ZERO);
+
+        breaker.record(true);
+        assert!(matches!(breaker.state.get(), BreakerState::Open { .. }));
+    }
+
+    #[test]
+    fn breaker_probes_after_the_cooldown_and_closes_on_success() {
+        let (breaker, clock) = breaker(1, 1);
+        breaker.record(true);
+        clock.advance(Duration::from_secs(10));
+        assert!(breaker.before_request().is_ok());
+        assert_secs(clock.take_slept(), 20.0);
+
+        breaker.record(false);
+        assert!(matches!(
+            breaker.state.get(),
+            BreakerState::Closed { failures: 0 }
+        ));
+        assert!(breaker.before_request().is_ok());
+        assert_eq!(clock.take_slept(), Duration::ZERO);
+    }
+
+    #[test]
+    fn breaker_stops_after_max_probes_and_defers_the_rest() {
+        let (breaker, clock) = breaker(1, 2);
+        breaker.record(true);
+
+        // First failed probe: the circuit opens again for another cooldown
+        assert!(breaker.before_request().is_ok());
+        assert_secs(clock.take_slept(), 30.0);
+        breaker.record(true);
+        assert!(matches!(
+            breaker.state.get(),
+            BreakerState::Open {
+                failed_probes: 1,
+                ..
+            }
+        ));
+
+        // Second failed probe: nothing else is sent
+        assert!(breaker.before_request().is_ok());
+        assert_secs(clock.take_slept(), 30.0);
+        breaker.record(true);
+        for _ in 0..3 {
+            let error = breaker.before_request().unwrap_err();
+            assert!(error.is::<DeferredError>());
+        }
+        assert_eq!(clock.take_slept(), Duration::ZERO);
+
+        // A late success does not reopen the circuit
+        breaker.record(false);
+        assert!(breaker.before_request().unwrap_err().is::<DeferredError>());
+    }
+}
@@ -0,0 +1,428 @@
+use crate::models::{activity_sections::Seccion, content::Content};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+/// How a translation memory entry matched a source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// The source text is identical to the entry.
+    Exact,
+    /// The source text matches the entry once case, spacing and trailing punctuation are ignored.
+    Normalized,
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchKind::Exact => write!(f, "exact match"),
+            MatchKind::Normalized => write!(f, "normalized match"),
+        }
+    }
+}
+
+/// A content field filled from the translation memory.
+#[derive(Debug)]
+pub struct FilledField {
+    /// Language that was filled (e.g., "EN").
+    pub language: String,
+
+    /// Content field that was filled ("title", "goal" or "script").
+    pub field: &'static str,
+
+    /// How the source text matched.
+    pub match_kind: MatchKind,
+}
+
+/// Offline translation memory used to fill empty content columns.
+///
+/// The JSON file holds a list of segments, each one mapping language codes to the
+/// same text in every language:
+///
+/// ```json
+/// { "entries": [ { "ES": "Pelotear suave", "EN": "Soft rally" } ] }
+/// ```
+///
+/// Files with the `.tmx` extension are read as TMX instead, one segment per `<tu>`:
+///
+/// ```xml
+/// <tu>
+///   <tuv xml:lang="es-ES"><seg>Pelotear suave</seg></tuv>
+///   <tuv xml:lang="en-GB"><seg>Soft rally</seg></tuv>
+/// </tu>
+/// ```
+#[derive(Debug, Deserialize, Default)]
+pub struct TranslationMemory {
+    /// Translation segments, keyed by ISO 639-1 code.
+    entries: Vec<HashMap<String, String>>,
+
+    /// Index from (language, normalized text) to the position of the entry.
+    #[serde(skip)]
+    index: HashMap<(String, String), usize>,
+}
+
+impl TranslationMemory {
+    /// Loads a translation memory from a JSON file, or from a TMX file when the path has
+    /// the `.tmx` extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the JSON or TMX file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result<TranslationMemory, Box<dyn Error>>` with the indexed segments.
+    pub fn load(path: &str) -> Result<TranslationMemory, Box<dyn Error>> {
+        let data = std::fs::read_to_string(path)?;
+        let is_tmx = std::path::Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("tmx"));
+        if is_tmx {
+            let entries =
+                parse_tmx(&data).map_err(|e| format!("Invalid TMX file {}: {}", path, e))?;
+            Ok(TranslationMemory::from_entries(entries))
+        } else {
+            let memory: TranslationMemory = serde_json::from_str(&data)?;
+            Ok(TranslationMemory::from_entries(memory.entries))
+        }
+    }
+
+    /// Builds the memory from its segments, indexing them by language and normalized text.
+    fn from_entries(entries: Vec<HashMap<String, String>>) -> TranslationMemory {
+        let mut memory = TranslationMemory {
+            entries,
+            index: HashMap::new(),
+        };
+
+        // Language codes are upper case everywhere else in the program
+        for entry in &mut memory.entries {
+            *entry = entry
+                .drain()
+                .map(|(code, text)| (code.trim().to_uppercase(), text))
+                .collect();
+        }
+        for (position, entry) in memory.entries.iter().enumerate() {
+            for (code, text) in entry {
+                memory
+                    .index
+                    .entry((code.clone(), normalize(text)))
+                    .or_insert(position);
+            }
+        }
+        memory
+    }
+
+    /// Number of segments in the memory.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the memory has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Looks up the translation of a text.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_language` - Language of `text`.
+    /// * `target_language` - Language to translate to.
+    /// * `text` - The text to translate.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<(&str, MatchKind)>` with the translated text and how it matched.
+    pub fn lookup(
+        &self,
+        source_language: &str,
+        target_language: &str,
+        text: &str,
+    ) -> Option<(&str, MatchKind)> {
+        if text.trim().is_empty() {
+            return None;
+        }
+        let position = self
+            .index
+            .get(&(source_language.to_string(), normalize(text)))?;
+        let entry = &self.entries[*position];
+        let translated = entry
+            .get(target_language)
+            .filter(|t| !t.trim().is_empty())?;
+        let match_kind = if entry.get(source_language).map(String::as_str) == Some(text) {
+            MatchKind::Exact
+        } else {
+            MatchKind::Normalized
+        };
+        Some((translated.as_str(), match_kind))
+    }
+
+    /// Fills the empty content fields of the target languages from the primary language.
+    ///
+    /// Languages that receive at least one field are marked as machine-filled.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of an activity, keyed by language.
+    /// * `primary_language` - The language the source texts are taken from.
+    /// * `target_languages` - The languages to fill.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<FilledField>` listing every field that was filled.
+    pub fn fill(
+        &self,
+        content: &mut HashMap<String, Content>,
+        primary_language: &str,
+        target_languages: &[&str],
+    ) -> Vec<FilledField> {
+        let Some(source) = content.get(primary_language).cloned() else {
+            return Vec::new();
+        };
+        let mut filled = Vec::new();
+
+        for &language in target_languages {
+            let target = content
+                .entry(language.to_string())
+                .or_insert_with(|| Content {
+                    title: String::new(),
+                    goal: String::new(),
+                    script: String::new(),
+                    machine_filled: false,
+                });
+            for (field, source_text, target_text) in [
+                ("title", &source.title, &mut target.title),
+                ("goal", &source.goal, &mut target.goal),
+                ("script", &source.script, &mut target.script),
+            ] {
+                if !target_text.trim().is_empty() {
+                    continue;
+                }
+                if let Some((translated, match_kind)) =
+                    self.lookup(primary_language, language, source_text)
+                {
+                    *target_text = translated.to_string();
+                    target.machine_filled = true;
+                    filled.push(FilledField {
+                        language: language.to_string(),
+                        field,
+                        match_kind,
+                    });
+                }
+            }
+            // Do not leave an empty language behind when nothing matched
+            if !target.machine_filled
+                && target.title.is_empty()
+                && target.goal.is_empty()
+                && target.script.is_empty()
+            {
+                content.remove(language);
+            }
+        }
+
+        filled
+    }
+}
+
+/// A source text that still needs a human translation.
+#[derive(Debug, Serialize)]
+pub struct PendingTranslation {
+    /// Language the text must be translated to.
+    pub language: String,
+
+    /// Text in the primary language.
+    pub text: String,
+
+    /// IDs of the activities using the text.
+    pub activities: Vec<String>,
+}
+
+/// Lists the primary language texts whose translation is still empty.
+///
+/// Each text is listed once per target language, with every activity that uses it.
+///
+/// # Arguments
+///
+/// * `secciones` - The parsed sections.
+/// * `primary_language` - The language the source texts are taken from.
+/// * `target_languages` - The languages that should be translated.
+///
+/// # Returns
+///
+/// A `Vec<PendingTranslation>` sorted by language and text.
+pub fn pending_translations(
+    secciones: &[Seccion],
+    primary_language: &str,
+    target_languages: &[&str],
+) -> Vec<PendingTranslation> {
+    let mut pending: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+
+    for actividad in secciones.iter().flat_map(Seccion::actividades) {
+        let Some(source) = actividad.content.get(primary_language) else {
+            continue;
+        };
+        for &language in target_languages {
+            let target = actividad.content.get(language);
+            for (source_text, target_text) in [
+                (&source.title, target.map(|c| &c.title)),
+                (&source.goal, target.map(|c| &c.goal)),
+                (&source.script, target.map(|c| &c.script)),
+            ] {
+                if !source_text.trim().is_empty() && target_text.is_none_or(|t| t.trim().is_empty())
+                {
+                    let activities = pending
+                        .entry((language.to_string(), source_text.trim().to_string()))
+                        .or_default();
+                    if !activities.contains(&actividad.id) {
+                        activities.push(actividad.id.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    pending
+        .into_iter()
+        .map(|((language, text), activities)| PendingTranslation {
+            language,
+            text,
+            activities,
+        })
+        .collect()
+}
+
+/// Reads the segments of a TMX document: one entry per translation unit (`<tu>`), with
+/// the text of the `<seg>` of each variant (`<tuv>`) keyed by its language. Regional
+/// subtags are dropped ("es-ES" is read as "ES"), and the content of inline markup such
+/// as `<ph>` or `<bpt>` is left out of the text.
+fn parse_tmx(data: &str) -> Result<Vec<HashMap<String, String>>, Box<dyn Error>> {
+    let mut reader = Reader::from_str(data);
+    let mut entries = Vec::new();
+    let mut entry: Option<HashMap<String, String>> = None;
+    let mut language: Option<String> = None;
+    let mut segment: Option<String> = None;
+    let mut inline_depth = 0;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(tag) => match tag.local_name().as_ref() {
+                b"tu" => entry = Some(HashMap::new()),
+                b"tuv" => language = tmx_language(&tag, &reader)?,
+                b"seg" => segment = Some(String::new()),
+                _ if segment.is_some() => inline_depth += 1,
+                _ => {}
+            },
+            Event::End(tag) => match tag.local_name().as_ref() {
+                b"tu" => entries.extend(entry.take().filter(|entry| !entry.is_empty())),
+                b"tuv" => language = None,
+                b"seg" => {
+                    let text = segment.take();
+                    if let (Some(entry), Some(language), Some(text)) =
+                        (entry.as_mut(), &language, text)
+                    {
+                        entry.insert(language.clone(), text);
+                    }
+                }
+                _ if segment.is_some() => inline_depth -= 1,
+                _ => {}
+            },
+            Event::Text(text) if inline_depth == 0 => {
+                if let Some(segment) = segment.as_mut() {
+                    segment.push_str(&text.unescape()?);
+                }
+            }
+            Event::CData(text) if inline_depth == 0 => {
+                if let Some(segment) = segment.as_mut() {
+                    segment.push_str(&String::from_utf8_lossy(&text.into_inner()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Language of a `<tuv>` element, from its `xml:lang` attribute (or `lang` in TMX 1.1),
+/// as an upper case ISO 639-1 code.
+fn tmx_language(
+    tag: &BytesStart,
+    reader: &Reader<&[u8]>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let attribute = match tag.try_get_attribute("xml:lang")? {
+        Some(attribute) => Some(attribute),
+        None => tag.try_get_attribute("lang")?,
+    };
+    let Some(attribute) = attribute else {
+        return Ok(None);
+    };
+    let value = attribute.decode_and_unescape_value(reader)?;
+    Ok(value
+        .split(['-', '_'])
+        .next()
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty()))
+}
+
+/// Normalizes a text for matching: lower case, single spaces and no trailing punctuation.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['.', '!', '?', ';', ':', ','])
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tmx version="1.4">
+  <header srclang="es-ES" datatype="plaintext" segtype="sentence" adminlang="en" o-tmf="none" creationtool="test" creationtoolversion="1"/>
+  <body>
+    <tu>
+      <tuv xml:lang="es-ES"><seg>Pelotear suave</seg></tuv>
+      <tuv xml:lang="en-GB"><seg>Soft rally</seg></tuv>
+      <tuv lang="FR"><seg>Échange doux</seg></tuv>
+    </tu>
+    <tu>
+      <tuv xml:lang="es"><seg>Golpear &amp; recuperar <ph x="1">&lt;b&gt;</ph>rápido</seg></tuv>
+      <tuv xml:lang="en"><seg><![CDATA[Hit & recover]]> quickly</seg></tuv>
+    </tu>
+    <tu>
+      <tuv><seg>Sin idioma</seg></tuv>
+    </tu>
+  </body>
+</tmx>"#;
+
+    #[test]
+    fn reads_tmx_segments() {
+        let memory = TranslationMemory::from_entries(parse_tmx(TMX).unwrap());
+        assert_eq!(memory.len(), 2);
+        assert_eq!(
+            memory.lookup("ES", "EN", "Pelotear suave"),
+            Some(("Soft rally", MatchKind::Exact))
+        );
+        assert_eq!(
+            memory.lookup("ES", "FR", "pelotear  suave."),
+            Some(("Échange doux", MatchKind::Normalized))
+        );
+        assert_eq!(
+            memory.lookup("ES", "EN", "Golpear & recuperar rápido"),
+            Some(("Hit & recover quickly", MatchKind::Exact))
+        );
+        assert_eq!(memory.lookup("ES", "EN", "Sin idioma"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_tmx() {
+        assert!(
+            parse_tmx("<tmx><body><tu><tuv xml:lang=\"es\"><seg>Hola</tuv></tu></body></tmx>")
+                .is_err()
+        );
+    }
+}
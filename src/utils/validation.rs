@@ -0,0 +1,683 @@
+use super::excel::block_field_offset;
+use super::layout::SheetLayout;
+use super::parser::{duration_minutes, MATERIAL_TERMS};
+use crate::models::{
+    activity::Actividad,
+    activity_sections::Seccion,
+    parse_report::Severity,
+    provenance::Provenance,
+    validation_report::{Finding, ValidationReport},
+};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// A validation rule and its parameters.
+///
+/// Rules are declared in the configuration file with a "rule" tag, e.g.
+/// `{ "rule": "player_range", "min": 1, "max": 8, "severity": "warning" }`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// The listed activity fields must not be empty (0 counts as empty for numbers).
+    RequiredFields { fields: Vec<String> },
+    /// The activity ID must match a regular expression.
+    IdFormat { pattern: String },
+    /// Activity IDs must be unique across every validated sheet.
+    UniqueIds,
+    /// Every player count must be within `min..=max`.
+    PlayerRange { min: i32, max: i32 },
+    /// The title, goal and script of a language must be filled. The language code is
+    /// uppercased when the rules are loaded, as in the content of the activities.
+    ContentNotEmpty {
+        #[serde(default = "default_language", deserialize_with = "uppercase")]
+        language: String,
+    },
+    /// The duration of an activity must not exceed the limit of its phase, in minutes.
+    /// Limits are keyed by phase ("MAIN_EXERCISE_2") or by phase kind ("MAIN_EXERCISE").
+    MaxDuration { limits: HashMap<String, u32> },
+    /// Equipment must come from the known material catalog, plus any extra entries.
+    KnownEquipment {
+        #[serde(default)]
+        extra: Vec<String>,
+    },
+}
+
+impl Rule {
+    /// Name of the rule, as used in the configuration file and the findings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::RequiredFields { .. } => "required_fields",
+            Rule::IdFormat { .. } => "id_format",
+            Rule::UniqueIds => "unique_ids",
+            Rule::PlayerRange { .. } => "player_range",
+            Rule::ContentNotEmpty { .. } => "content_not_empty",
+            Rule::MaxDuration { .. } => "max_duration",
+            Rule::KnownEquipment { .. } => "known_equipment",
+        }
+    }
+}
+
+/// A rule together with the severity of its findings.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RuleConfig {
+    /// The rule to apply.
+    #[serde(flatten)]
+    pub rule: Rule,
+
+    /// Severity of the findings produced by the rule.
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+}
+
+/// Set of rules applied by the validate command.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ValidationConfig {
+    /// Rules, applied in order.
+    pub rules: Vec<RuleConfig>,
+}
+
+fn default_language() -> String {
+    "ES".to_string()
+}
+
+fn uppercase<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(String::deserialize(deserializer)?.to_uppercase())
+}
+
+fn default_severity() -> Severity {
+    Severity::Error
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        let rule = |rule, severity| RuleConfig { rule, severity };
+        ValidationConfig {
+            rules: vec![
+                rule(
+                    Rule::RequiredFields {
+                        fields: [
+                            "id",
+                            "golpe",
+                            "num_jugadores",
+                            "model",
+                            "shot",
+                            "part_to_practice",
+                            "duration",
+                        ]
+                        .iter()
+                        .map(|field| field.to_string())
+                        .collect(),
+                    },
+                    Severity::Error,
+                ),
+                rule(
+                    Rule::IdFormat {
+                        pattern: "^[A-Za-z0-9][A-Za-z0-9._-]*$".to_string(),
+                    },
+                    Severity::Error,
+                ),
+                rule(Rule::UniqueIds, Severity::Error),
+                rule(Rule::PlayerRange { min: 1, max: 8 }, Severity::Warning),
+                rule(
+                    Rule::ContentNotEmpty {
+                        language: default_language(),
+                    },
+                    Severity::Error,
+                ),
+                rule(
+                    Rule::MaxDuration {
+                        limits: [("WARM_UP", 20), ("MAIN_EXERCISE", 40), ("FINAL_PART", 20)]
+                            .iter()
+                            .map(|(phase, minutes)| (phase.to_string(), *minutes))
+                            .collect(),
+                    },
+                    Severity::Warning,
+                ),
+                rule(
+                    Rule::KnownEquipment { extra: Vec::new() },
+                    Severity::Warning,
+                ),
+            ],
+        }
+    }
+}
+
+/// Loads a validation configuration from a JSON file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON file.
+///
+/// # Returns
+///
+/// A `Result<ValidationConfig, Box<dyn Error>>` with the configured rules.
+pub fn load_validation_config(path: &str) -> Result<ValidationConfig, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let config: ValidationConfig = serde_json::from_str(&data)?;
+    Ok(config)
+}
+
+/// Runs the configured rules over the activities of one or more sections.
+///
+/// # Arguments
+///
+/// * `secciones` - The sections to validate, usually one per sheet.
+/// * `config` - The rules to apply.
+/// * `layout` - The layout the sections were read with, used to locate content cells.
+///
+/// # Returns
+///
+/// A `Result<ValidationReport, Box<dyn Error>>` with the findings, or an error if a rule is misconfigured.
+pub fn validate_secciones(
+    secciones: &[Seccion],
+    config: &ValidationConfig,
+    layout: &SheetLayout,
+) -> Result<ValidationReport, Box<dyn Error>> {
+    let actividades: Vec<&Actividad> = secciones.iter().flat_map(Seccion::actividades).collect();
+    let mut report = ValidationReport {
+        checked: actividades.len(),
+        ..Default::default()
+    };
+
+    for rule_config in &config.rules {
+        let mut findings = Vec::new();
+        match &rule_config.rule {
+            Rule::RequiredFields { fields } => {
+                check_required_fields(&actividades, fields, &mut findings)?
+            }
+            Rule::IdFormat { pattern } => {
+                let regex = Regex::new(pattern)?;
+                for &actividad in &actividades {
+                    if !regex.is_match(&actividad.id) {
+                        findings.push((
+                            actividad,
+                            field_location(actividad, "id"),
+                            format!("id does not match '{}'", pattern),
+                        ));
+                    }
+                }
+            }
+            Rule::UniqueIds => {
+                let mut seen: HashMap<&str, Option<&Provenance>> = HashMap::new();
+                for &actividad in &actividades {
+                    if actividad.id.is_empty() {
+                        continue;
+                    }
+                    match seen.get(actividad.id.as_str()) {
+                        Some(first) => findings.push((
+                            actividad,
+                            field_location(actividad, "id"),
+                            match first {
+                                Some(first) => format!("id already used at {}", first),
+                                None => "id already used".to_string(),
+                            },
+                        )),
+                        None => {
+                            seen.insert(&actividad.id, actividad.provenance.as_ref());
+                        }
+                    }
+                }
+            }
+            Rule::PlayerRange { min, max } => {
+                for &actividad in &actividades {
+                    let jugadores = &actividad.num_jugadores;
+                    let (Some(lowest), Some(highest)) = (jugadores.min(), jugadores.max()) else {
+                        continue;
+                    };
+                    if lowest < *min || highest > *max {
+                        findings.push((
+                            actividad,
+                            field_location(actividad, "num_jugadores"),
+                            format!("player count {} outside {}..={}", jugadores, min, max),
+                        ));
+                    }
+                }
+            }
+            Rule::ContentNotEmpty { language } => {
+                for &actividad in &actividades {
+                    let content = actividad.content.get(language);
+                    for (field, value) in [
+                        ("title", content.map(|c| c.title.as_str())),
+                        ("goal", content.map(|c| c.goal.as_str())),
+                        ("script", content.map(|c| c.script.as_str())),
+                    ] {
+                        if value.is_none_or(|v| v.trim().is_empty()) {
+                            let location = actividad.provenance.as_ref().map(|provenance| {
+                                match layout.content_offset(language, field) {
+                                    Some(offset) => provenance.cell(offset),
+                                    None => provenance.clone(),
+                                }
+                            });
+                            findings.push((
+                                actividad,
+                                location,
+                                format!("{} {} is empty", language, field),
+                            ));
+                        }
+                    }
+                }
+            }
+            Rule::MaxDuration { limits } => {
+                for &actividad in &actividades {
+                    let phase = &actividad.phase;
+                    let Some(limit) = limits
+                        .get(&phase.to_string())
+                        .or_else(|| limits.get(phase.kind()))
+                    else {
+                        continue;
+                    };
+                    match duration_minutes(&actividad.duration) {
+                        Some(minutes) if minutes > *limit => findings.push((
+                            actividad,
+                            field_location(actividad, "duration"),
+                            format!(
+                                "duration {}m exceeds the {}m limit of {}",
+                                minutes, limit, actividad.phase
+                            ),
+                        )),
+                        Some(_) => {}
+                        None => findings.push((
+                            actividad,
+                            field_location(actividad, "duration"),
+                            format!("cannot read minutes from duration '{}'", actividad.duration),
+                        )),
+                    }
+                }
+            }
+            Rule::KnownEquipment { extra } => {
+                for &actividad in &actividades {
+                    for item in &actividad.equipment {
+                        let known = MATERIAL_TERMS
+                            .iter()
+                            .map(|(_, english)| *english)
+                            .chain(extra.iter().map(String::as_str))
+                            .any(|known| known.eq_ignore_ascii_case(item));
+                        if !item.is_empty() && !known {
+                            findings.push((
+                                actividad,
+                                field_location(actividad, "equipment"),
+                                format!("'{}' is not in the equipment catalog", item),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (actividad, location, message) in findings {
+            report.findings.push(Finding {
+                rule: rule_config.rule.name().to_string(),
+                severity: rule_config.severity,
+                activity: actividad.id.clone(),
+                location,
+                message,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Checks that the listed fields of each activity are not empty.
+fn check_required_fields<'a>(
+    actividades: &[&'a Actividad],
+    fields: &[String],
+    findings: &mut Vec<(&'a Actividad, Option<Provenance>, String)>,
+) -> Result<(), Box<dyn Error>> {
+    for &actividad in actividades {
+        let value = serde_json::to_value(actividad)?;
+        for field in fields {
+            let field_value = value
+                .get(field)
+                .ok_or_else(|| format!("Unknown field in required_fields rule: {}", field))?;
+            if is_empty_value(field_value) {
+                findings.push((
+                    actividad,
+                    field_location(actividad, field),
+                    format!("{} is empty", field),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` for null, blank strings, 0, and arrays or objects with no non-empty value.
+fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::Number(n) => n.as_f64() == Some(0.0),
+        Value::Array(items) => items.iter().all(is_empty_value),
+        Value::Object(map) => map.values().all(is_empty_value),
+        Value::Bool(_) => false,
+    }
+}
+
+/// Returns the cell of a field when it lives in the activity block, or the whole block otherwise.
+fn field_location(actividad: &Actividad, field: &str) -> Option<Provenance> {
+    let provenance = actividad.provenance.as_ref()?;
+    Some(match block_field_offset(field) {
+        Some(offset) => provenance.cell(offset),
+        None => provenance.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::activity_sections::Fase;
+    use crate::models::phase::Phase;
+    use serde_json::json;
+
+    fn actividad(id: &str, phase: Phase, row: usize) -> Actividad {
+        let mut actividad: Actividad = serde_json::from_value(json!({
+            "id": id,
+            "golpe": 1,
+            "phase": phase,
+            "num_jugadores": [2, 4],
+            "typology": ["IMPROVEMENT"],
+            "level": ["EASY"],
+            "model": ["TECHNIQUE"],
+            "shot": ["FOREHAND"],
+            "part_to_practice": ["DEPTH"],
+            "equipment": ["CONES"],
+            "duration": "10m",
+            "content": {
+                "ES": { "title": "Calentar", "goal": "Activar", "script": "Pelotear suave" }
+            }
+        }))
+        .unwrap();
+        actividad.provenance = Some(Provenance::new("es.xlsx", "Derecha", row, 4, 18));
+        actividad
+    }
+
+    fn seccion(actividades: Vec<Actividad>) -> Seccion {
+        Seccion {
+            golpe: None,
+            fases: vec![Fase {
+                phase: Phase::WarmUp,
+                actividades,
+            }],
+        }
+    }
+
+    fn config(rules: Value) -> ValidationConfig {
+        serde_json::from_value(json!({ "rules": rules })).unwrap()
+    }
+
+    /// Runs the rules and lists the findings as (rule, activity, location, message).
+    fn findings(
+        actividades: Vec<Actividad>,
+        rules: Value,
+    ) -> Vec<(String, String, String, String)> {
+        validate_secciones(
+            &[seccion(actividades)],
+            &config(rules),
+            &SheetLayout::default(),
+        )
+        .unwrap()
+        .findings
+        .into_iter()
+        .map(|finding| {
+            (
+                finding.rule,
+                finding.activity,
+                finding
+                    .location
+                    .map(|location| location.columns)
+                    .unwrap_or_default(),
+                finding.message,
+            )
+        })
+        .collect()
+    }
+
+    fn finding(
+        rule: &str,
+        activity: &str,
+        location: &str,
+        message: &str,
+    ) -> (String, String, String, String) {
+        (
+            rule.to_string(),
+            activity.to_string(),
+            location.to_string(),
+            message.to_string(),
+        )
+    }
+
+    #[test]
+    fn reports_empty_required_fields() {
+        let mut empty = actividad("D-C2", Phase::WarmUp, 6);
+        empty.shot.clear();
+        empty.golpe = 0;
+        assert_eq!(
+            findings(
+                vec![actividad("D-C1", Phase::WarmUp, 5), empty],
+                json!([{ "rule": "required_fields", "fields": ["golpe", "shot"] }])
+            ),
+            [
+                finding("required_fields", "D-C2", "E6:S6", "golpe is empty"),
+                finding("required_fields", "D-C2", "G6", "shot is empty"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_required_fields() {
+        let rules = config(json!([{ "rule": "required_fields", "fields": ["colour"] }]));
+        let error = validate_secciones(
+            &[seccion(vec![actividad("D-C1", Phase::WarmUp, 5)])],
+            &rules,
+            &SheetLayout::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown field in required_fields rule: colour"
+        );
+    }
+
+    #[test]
+    fn checks_the_id_format() {
+        assert_eq!(
+            findings(
+                vec![
+                    actividad("D-C1", Phase::WarmUp, 5),
+                    actividad("D C2", Phase::WarmUp, 6)
+                ],
+                json!([{ "rule": "id_format", "pattern": "^[A-Z]-C[0-9]+$" }])
+            ),
+            [finding(
+                "id_format",
+                "D C2",
+                "E6",
+                "id does not match '^[A-Z]-C[0-9]+$'"
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_repeated_ids_with_their_first_location() {
+        assert_eq!(
+            findings(
+                vec![
+                    actividad("D-C1", Phase::WarmUp, 5),
+                    actividad("D-C1", Phase::WarmUp, 6),
+                    actividad("", Phase::WarmUp, 7),
+                    actividad("", Phase::WarmUp, 8),
+                ],
+                json!([{ "rule": "unique_ids" }])
+            ),
+            [finding(
+                "unique_ids",
+                "D-C1",
+                "E6",
+                "id already used at es.xlsx 'Derecha'!E5:S5"
+            )]
+        );
+    }
+
+    #[test]
+    fn checks_the_player_range() {
+        let mut large = actividad("D-C2", Phase::WarmUp, 6);
+        large.num_jugadores = [4, 12].into_iter().collect();
+        let mut unknown = actividad("D-C3", Phase::WarmUp, 7);
+        unknown.num_jugadores = Default::default();
+        assert_eq!(
+            findings(
+                vec![actividad("D-C1", Phase::WarmUp, 5), large, unknown],
+                json!([{ "rule": "player_range", "min": 2, "max": 8 }])
+            ),
+            [finding(
+                "player_range",
+                "D-C2",
+                "E6:S6",
+                "player count 4, 12 outside 2..=8"
+            )]
+        );
+    }
+
+    #[test]
+    fn checks_the_content_of_the_language_in_any_case() {
+        let mut untitled = actividad("D-C2", Phase::WarmUp, 6);
+        untitled.content.get_mut("ES").unwrap().title = " ".to_string();
+        for language in ["ES", "es"] {
+            assert_eq!(
+                findings(
+                    vec![actividad("D-C1", Phase::WarmUp, 5), untitled.clone()],
+                    json!([{ "rule": "content_not_empty", "language": language }])
+                ),
+                [finding(
+                    "content_not_empty",
+                    "D-C2",
+                    "L6",
+                    "ES title is empty"
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn reports_every_field_of_a_missing_language() {
+        let messages: Vec<String> = findings(
+            vec![actividad("D-C1", Phase::WarmUp, 5)],
+            json!([{ "rule": "content_not_empty", "language": "en" }]),
+        )
+        .into_iter()
+        .map(|(_, _, _, message)| message)
+        .collect();
+        assert_eq!(
+            messages,
+            [
+                "EN title is empty",
+                "EN goal is empty",
+                "EN script is empty"
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_the_duration_limit_of_each_phase() {
+        let mut long_warm_up = actividad("D-C1", Phase::WarmUp, 5);
+        long_warm_up.duration = "25m".to_string();
+        let mut long_main = actividad("D-C2", Phase::MainExercise { index: 2 }, 6);
+        long_main.duration = "35m".to_string();
+        let mut unreadable = actividad("D-C3", Phase::MainExercise { index: 1 }, 7);
+        unreadable.duration = "a while".to_string();
+        let mut final_part = actividad("D-C4", Phase::FinalPart, 8);
+        final_part.duration = "90m".to_string();
+        assert_eq!(
+            findings(
+                vec![long_warm_up, long_main, unreadable, final_part],
+                json!([{
+                    "rule": "max_duration",
+                    "limits": { "WARM_UP": 20, "MAIN_EXERCISE": 40, "MAIN_EXERCISE_2": 30 }
+                }])
+            ),
+            [
+                finding(
+                    "max_duration",
+                    "D-C1",
+                    "J5",
+                    "duration 25m exceeds the 20m limit of WARM_UP"
+                ),
+                finding(
+                    "max_duration",
+                    "D-C2",
+                    "J6",
+                    "duration 35m exceeds the 30m limit of MAIN_EXERCISE_2"
+                ),
+                finding(
+                    "max_duration",
+                    "D-C3",
+                    "J7",
+                    "cannot read minutes from duration 'a while'"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_the_equipment_catalog_and_its_extra_entries() {
+        let mut unusual = actividad("D-C2", Phase::WarmUp, 6);
+        unusual.equipment = vec![
+            "cones".to_string(),
+            "Ladder".to_string(),
+            "Drone".to_string(),
+        ];
+        assert_eq!(
+            findings(
+                vec![actividad("D-C1", Phase::WarmUp, 5), unusual],
+                json!([{ "rule": "known_equipment", "extra": ["LADDER"] }])
+            ),
+            [finding(
+                "known_equipment",
+                "D-C2",
+                "I6",
+                "'Drone' is not in the equipment catalog"
+            )]
+        );
+    }
+
+    #[test]
+    fn fails_only_on_findings_with_error_severity() {
+        let mut invalid = actividad("D C1", Phase::WarmUp, 5);
+        invalid.num_jugadores = [12].into_iter().collect();
+        let secciones = [seccion(vec![invalid])];
+
+        let warnings = config(json!([
+            { "rule": "player_range", "min": 1, "max": 8, "severity": "warning" },
+            { "rule": "id_format", "pattern": "^\\S+$", "severity": "info" }
+        ]));
+        let report = validate_secciones(&secciones, &warnings, &SheetLayout::default()).unwrap();
+        assert_eq!(report.count(Severity::Warning), 1);
+        assert_eq!(report.count(Severity::Info), 1);
+        assert!(!report.has_errors());
+
+        // Rules are errors unless configured otherwise
+        let errors = config(json!([{ "rule": "player_range", "min": 1, "max": 8 }]));
+        let report = validate_secciones(&secciones, &errors, &SheetLayout::default()).unwrap();
+        assert_eq!(report.count(Severity::Error), 1);
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn default_rules_accept_a_complete_activity() {
+        let report = validate_secciones(
+            &[seccion(vec![actividad("D-C1", Phase::WarmUp, 5)])],
+            &ValidationConfig::default(),
+            &SheetLayout::default(),
+        )
+        .unwrap();
+        assert_eq!(report.checked, 1);
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+    }
+}
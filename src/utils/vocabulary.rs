@@ -0,0 +1,296 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Minimum confidence for a known term to be suggested for an unknown one.
+const MIN_SUGGESTION_CONFIDENCE: f64 = 0.5;
+
+/// Connecting words ignored when comparing terms, so that "PARED LATERAL DE REVÉS"
+/// matches "PARED LATERAL REVÉS".
+const CONNECTING_WORDS: [&str; 5] = ["DE", "DEL", "LA", "EL", "A"];
+
+/// Settings that control how the terms of the sheet are matched against the translation tables.
+#[derive(Debug, Clone)]
+pub struct VocabularyOptions {
+    /// Whether accents are ignored when comparing terms ("TECNICA" matches "TÉCNICA").
+    pub fold_accents: bool,
+
+    /// Confidence (0 to 1) from which an unknown term is replaced by the suggested one.
+    /// When `None`, unknown terms are only reported.
+    pub autocorrect_threshold: Option<f64>,
+}
+
+impl Default for VocabularyOptions {
+    fn default() -> Self {
+        VocabularyOptions {
+            fold_accents: true,
+            autocorrect_threshold: None,
+        }
+    }
+}
+
+/// Known term proposed for a term that has no entry in a translation table.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// The known term, as written in the table.
+    pub term: String,
+
+    /// Translation of the known term.
+    pub translation: String,
+
+    /// Similarity between both terms, from 0 to 1.
+    pub confidence: f64,
+}
+
+/// Result of looking up a term in a translation table.
+#[derive(Debug)]
+pub enum TermMatch {
+    /// The term is an entry of the table; holds its translation.
+    Known(String),
+    /// The term is already one of the translated values; holds the value as written in the table.
+    Translated(String),
+    /// The term is unknown but close to an entry of the table.
+    Nearest(Suggestion),
+    /// The term is unknown and nothing in the table is close to it.
+    Unknown,
+}
+
+/// Looks up a term in a translation table.
+///
+/// Terms are compared in their canonical form (see `canonical_term`). When there is
+/// no match, the closest Spanish or English term of the table by edit distance is
+/// returned as a suggestion.
+///
+/// # Arguments
+///
+/// * `term` - The term to look up.
+/// * `terms` - The translation table, as (Spanish, English) pairs.
+/// * `options` - The vocabulary matching settings.
+///
+/// # Returns
+///
+/// A `TermMatch` describing how the term matched.
+pub fn find_term(term: &str, terms: &[(&str, &str)], options: &VocabularyOptions) -> TermMatch {
+    let canonical = canonical_term(term, options);
+    if canonical.is_empty() {
+        return TermMatch::Unknown;
+    }
+
+    let mut nearest: Option<Suggestion> = None;
+    for (spanish, english) in terms {
+        if canonical_term(spanish, options) == canonical {
+            return TermMatch::Known(english.to_string());
+        }
+        if canonical_term(english, options) == canonical {
+            return TermMatch::Translated(english.to_string());
+        }
+        for known in [spanish, english] {
+            let confidence = similarity(&canonical, &canonical_term(known, options));
+            if nearest.as_ref().is_none_or(|n| confidence > n.confidence) {
+                nearest = Some(Suggestion {
+                    term: known.to_string(),
+                    translation: english.to_string(),
+                    confidence,
+                });
+            }
+        }
+    }
+
+    match nearest {
+        Some(suggestion) if suggestion.confidence >= MIN_SUGGESTION_CONFIDENCE => {
+            TermMatch::Nearest(suggestion)
+        }
+        _ => TermMatch::Unknown,
+    }
+}
+
+/// Returns the form used to compare terms.
+///
+/// The term is normalized to NFC and uppercase, accents are removed when
+/// `fold_accents` is set, connecting words are dropped, whitespace is collapsed and
+/// each word is reduced to its singular form.
+pub fn canonical_term(term: &str, options: &VocabularyOptions) -> String {
+    let mut text: String = term.nfc().collect::<String>().to_uppercase();
+    if options.fold_accents {
+        text = text
+            .nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .nfc()
+            .collect();
+    }
+    text.split_whitespace()
+        .filter(|word| !CONNECTING_WORDS.contains(word))
+        .map(singular)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reduces an uppercase Spanish word to the stem shared by its singular and plural forms
+/// ("CONOS" → "CONO", "PAREDES" → "PARED").
+///
+/// A plural in "-ES" after L, N, R, D, J or Y may come from a singular with or without a
+/// final "E" ("DOBLES" from "DOBLE", "LATERALES" from "LATERAL"), so that "E" is dropped
+/// from the singular too: "DOBLE" and "DOBLES" both become "DOBL". The stem is only a
+/// comparison key and may not be a word.
+fn singular(word: &str) -> String {
+    let mut chars: Vec<char> = word.chars().collect();
+    if chars.len() <= 3 {
+        return word.to_string();
+    }
+    if chars.last() == Some(&'S') {
+        chars.pop();
+    }
+    if chars.len() > 2 && chars.last() == Some(&'E') && "LNRDJY".contains(chars[chars.len() - 2]) {
+        chars.pop();
+    }
+    chars.into_iter().collect()
+}
+
+/// Similarity of two strings from 0 (nothing in common) to 1 (equal), based on the edit distance.
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TERMS: [(&str, &str); 4] = [
+        ("PARED LATERAL", "SIDE_WALL"),
+        ("DOBLE PARED", "DOUBLE_WALL"),
+        ("TÉCNICA", "TECHNIQUE"),
+        ("CONO", "CONES"),
+    ];
+
+    fn folding() -> VocabularyOptions {
+        VocabularyOptions::default()
+    }
+
+    fn strict() -> VocabularyOptions {
+        VocabularyOptions {
+            fold_accents: false,
+            ..VocabularyOptions::default()
+        }
+    }
+
+    #[test]
+    fn singular_and_plural_share_a_stem() {
+        for (singular_form, plural_form) in [
+            ("CONO", "CONOS"),
+            ("PAREJA", "PAREJAS"),
+            ("DOBLE", "DOBLES"),
+            ("GRANDE", "GRANDES"),
+            ("PARED", "PAREDES"),
+            ("LATERAL", "LATERALES"),
+            ("JOVEN", "JOVENES"),
+            ("NIVEL", "NIVELES"),
+            ("CLASE", "CLASES"),
+            ("TRIO", "TRIOS"),
+        ] {
+            assert_eq!(
+                singular(singular_form),
+                singular(plural_form),
+                "{}",
+                plural_form
+            );
+        }
+        assert_eq!(singular("PAREDES"), "PARED");
+        assert_eq!(singular("CONOS"), "CONO");
+        // Short words are kept as written
+        assert_eq!(singular("DOS"), "DOS");
+        assert_eq!(singular("RED"), "RED");
+    }
+
+    #[test]
+    fn canonical_term_ignores_case_spacing_plurals_and_connecting_words() {
+        assert_eq!(
+            canonical_term("  paredes   laterales de  revés ", &folding()),
+            canonical_term("PARED LATERAL REVÉS", &folding())
+        );
+        assert_eq!(
+            canonical_term("Dobles", &folding()),
+            canonical_term("DOBLE", &folding())
+        );
+        assert_eq!(canonical_term("de la", &folding()), "");
+    }
+
+    #[test]
+    fn canonical_term_folds_accents_unless_disabled() {
+        assert_eq!(canonical_term("técnica", &folding()), "TECNICA");
+        assert_eq!(canonical_term("TECNICA", &folding()), "TECNICA");
+        assert_eq!(canonical_term("técnica", &strict()), "TÉCNICA");
+        // Decomposed accents are composed before comparing
+        assert_eq!(
+            canonical_term("te\u{301}cnica", &strict()),
+            canonical_term("técnica", &strict())
+        );
+    }
+
+    #[test]
+    fn find_term_matches_known_and_translated_terms() {
+        assert!(matches!(
+            find_term("paredes laterales", &TERMS, &folding()),
+            TermMatch::Known(english) if english == "SIDE_WALL"
+        ));
+        assert!(matches!(
+            find_term("DOBLES PAREDES", &TERMS, &folding()),
+            TermMatch::Known(english) if english == "DOUBLE_WALL"
+        ));
+        assert!(matches!(
+            find_term("technique", &TERMS, &folding()),
+            TermMatch::Translated(english) if english == "TECHNIQUE"
+        ));
+        assert!(matches!(
+            find_term("", &TERMS, &folding()),
+            TermMatch::Unknown
+        ));
+    }
+
+    #[test]
+    fn find_term_respects_accent_folding() {
+        assert!(matches!(
+            find_term("TECNICA", &TERMS, &folding()),
+            TermMatch::Known(english) if english == "TECHNIQUE"
+        ));
+        match find_term("TECNICA", &TERMS, &strict()) {
+            TermMatch::Nearest(suggestion) => {
+                assert_eq!(suggestion.term, "TÉCNICA");
+                assert!(suggestion.confidence > 0.8 && suggestion.confidence < 1.0);
+            }
+            other => panic!("expected a suggestion, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn find_term_suggests_the_nearest_term_above_the_minimum_confidence() {
+        match find_term("TECNICAA", &TERMS, &folding()) {
+            TermMatch::Nearest(suggestion) => {
+                assert_eq!(suggestion.term, "TÉCNICA");
+                assert_eq!(suggestion.translation, "TECHNIQUE");
+                assert!((suggestion.confidence - 7.0 / 8.0).abs() < 1e-9);
+            }
+            other => panic!("expected a suggestion, found {:?}", other),
+        }
+        assert!(matches!(
+            find_term("RAQUETA", &TERMS, &folding()),
+            TermMatch::Unknown
+        ));
+    }
+}